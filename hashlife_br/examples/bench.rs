@@ -0,0 +1,167 @@
+//! 比較 `Rc` 版、arena 版與多執行緒版 HashLife 的速度
+//!
+//! ```text
+//! cargo run --release --example bench -- [世代數] [RLE 檔案...]
+//! ```
+//!
+//! 未指定檔案時使用內建圖樣：
+//!
+//! - 滑翔機槍陣列：沒有 eater 的 Gosper 槍排成 8x8，滑翔機一路往右下方飛
+//! - 吃掉滑翔機的槍陣列：每支槍配一個 eater，整體是週期 30 的大型規則結構，與 metacell 一樣是 HashLife 最擅長的情形
+//! - switch engine：39 格的單列圖樣長成兩個不斷鋪方塊的 switch engine，前期混亂、之後線性成長
+//! - 隨機圖樣：128x128、密度 35% 的混沌圖樣，快取命中率最低
+//!
+//! 另外會從 conwaylife.com 的圖樣庫下載 breeder 與 metacell 兩個外部圖樣（二次成長與超大週期結構），
+//! 快取在 `examples/patterns/`；下載失敗時略過並印出原因。其他圖樣可用 RLE 檔案傳入，標頭中的規則也會套用。
+//!
+//! 參考結果：1 個 CPU 核心（多執行緒版只有一條工作執行緒）、release 建置、4096 代，
+//! 括號內為最後的節點數，三種實作相同。量測時沒有網路，breeder 與 metacell 兩列未能下載，表中沒有數字：
+//!
+//! ```text
+//! gosper gun 8x8                 4096 gens  pop       6024  Rc     1.13s (  464267 nodes, hit 91.3%)  arena  446.79ms (  464267 nodes)  parallel     2.12s (  464267 nodes)
+//! eaten gun lattice 32x32        4096 gens  pop      59392  Rc  211.26ms (  240018 nodes, hit 91.7%)  arena   39.10ms (  240018 nodes)  parallel  161.47ms (  240018 nodes)
+//! switch engines                 4096 gens  pop       1164  Rc  119.23ms (   95271 nodes, hit 89.4%)  arena   48.62ms (   95271 nodes)  parallel  228.98ms (   95271 nodes)
+//! soup 128x128                   4096 gens  pop        907  Rc  670.27ms (  380933 nodes, hit 91.0%)  arena  373.30ms (  380933 nodes)  parallel     1.76s (  380933 nodes)
+//! breeder 1: download failed (exit status: 6)
+//! OTCA metapixel: download failed (exit status: 6)
+//! ```
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+use hashlife_br::{ArenaUniverse, ParallelUniverse, Rule, RlePattern, Universe};
+
+const GOSPER_GUN: &str = "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!";
+/// 放在槍左上角 (30, 16) 處，剛好吃掉每一隻滑翔機
+const EATER: &str = "2o$obo$2bo$2b2o!";
+const SWITCH_ENGINES: &str = "8ob5o3b3o6b7ob5o!";
+/// 未指定檔案時下載的外部圖樣（名稱, 快取檔名）
+const REMOTE: [(&str, &str); 2] = [("breeder 1", "breeder1.rle"), ("OTCA metapixel", "otcametapixel.rle")];
+const REMOTE_URL: &str = "https://conwaylife.com/patterns/";
+
+fn parse(rle: &str) -> Vec<(i64, i64)> {
+    RlePattern::parse(rle).expect("內建圖樣").cells
+}
+
+/// `n`x`n` 個相隔 80 格的圖樣
+fn tile(cells: &[(i64, i64)], n: i64) -> Vec<(i64, i64)> {
+    let mut tiled = Vec::new();
+    for gy in 0..n {
+        for gx in 0..n {
+            tiled.extend(cells.iter().map(|&(x, y)| (x + gx * 80, y + gy * 80)));
+        }
+    }
+    tiled
+}
+
+fn eaten_gun() -> Vec<(i64, i64)> {
+    let mut cells = parse(GOSPER_GUN);
+    cells.extend(parse(EATER).into_iter().map(|(x, y)| (x + 30, y + 16)));
+    cells
+}
+
+fn soup(size: i64) -> Vec<(i64, i64)> {
+    let mut state = 0x2545f4914f6cdd1du64;
+    let mut cells = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if state % 100 < 35 {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// 讀取快取的外部圖樣，沒有快取時用 curl 下載
+fn fetch(file: &str) -> Result<String, String> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/patterns");
+    let path = dir.join(file);
+    if !path.exists() {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let status = Command::new("curl")
+            .args(["-sfL", "--max-time", "30", "-o"])
+            .arg(&path)
+            .arg(format!("{}{}", REMOTE_URL, file))
+            .status()
+            .map_err(|e| format!("cannot run curl: {}", e))?;
+        if !status.success() {
+            let _ = std::fs::remove_file(&path);
+            return Err(format!("download failed ({})", status));
+        }
+    }
+    std::fs::read_to_string(&path).map_err(|e| e.to_string())
+}
+
+fn bench(name: &str, pattern: &RlePattern, generations: u64) {
+    let start = Instant::now();
+    let mut rc = Universe::with_rule(pattern.rule.clone());
+    for &(x, y) in &pattern.cells {
+        rc.set_cell(x, y, true).unwrap();
+    }
+    rc.step(generations).unwrap();
+    let rc_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut arena = ArenaUniverse::with_rule(pattern.rule.clone());
+    for &(x, y) in &pattern.cells {
+        arena.set_cell(x, y, true).unwrap();
+    }
+    arena.step(generations).unwrap();
+    let arena_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut parallel = ParallelUniverse::with_rule(pattern.rule.clone());
+    for &(x, y) in &pattern.cells {
+        parallel.set_cell(x, y, true).unwrap();
+    }
+    parallel.step(generations).unwrap();
+    let parallel_time = start.elapsed();
+
+    assert_eq!(rc.population(), arena.population());
+    assert_eq!(rc.population(), parallel.population());
+    let stats = rc.stats();
+    println!(
+        "{:<24} {:>10} gens  pop {:>10}  Rc {:>9.2?} ({:>8} nodes, hit {:.1}%)  arena {:>9.2?} ({:>8} nodes)  parallel {:>9.2?} ({:>8} nodes)",
+        name,
+        generations,
+        rc.population(),
+        rc_time,
+        stats.nodes,
+        stats.hit_rate() * 100.0,
+        arena_time,
+        arena.stats().nodes,
+        parallel_time,
+        parallel.node_count(),
+    );
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let generations = args.next().and_then(|g| g.parse().ok()).unwrap_or(1 << 12);
+    let files: Vec<String> = args.collect();
+
+    if files.is_empty() {
+        let life = |cells| RlePattern { cells, rule: Rule::default() };
+        bench("gosper gun 8x8", &life(tile(&parse(GOSPER_GUN), 8)), generations);
+        bench("eaten gun lattice 32x32", &life(tile(&eaten_gun(), 32)), generations);
+        bench("switch engines", &life(parse(SWITCH_ENGINES)), generations);
+        bench("soup 128x128", &life(soup(128)), generations);
+        for (name, file) in REMOTE {
+            match fetch(file).and_then(|text| RlePattern::parse(&text).map_err(|e| e.to_string())) {
+                Ok(pattern) => bench(name, &pattern, generations),
+                Err(e) => eprintln!("{}: {}", name, e),
+            }
+        }
+    }
+    for path in files {
+        match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| RlePattern::parse(&text).map_err(|e| e.to_string())) {
+            Ok(pattern) => bench(&path, &pattern, generations),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
+}
@@ -0,0 +1,568 @@
+//! 以扁平陣列存放節點的 HashLife 實作
+//!
+//! 節點以 u32 編號互相參照，hash-consing 與跳躍結果各用一張開放定址雜湊表，
+//! 不需要 `Rc` 的引用計數，也讓節點在記憶體中連續排列。演算法與 [`crate::Universe`] 相同。
+//! 超過記憶體預算時把可到達的節點複製到新的 arena 並重新編號，其餘一次丟棄。
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+use std::{io, mem};
+
+use crate::{check_level, check_step, MacrocellError, Node, RangeError, Rule, Stats, Universe};
+
+/// 節點編號，0 與 1 固定為死、活 leaf
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+/// 雜湊表中的空槽
+const EMPTY: NodeId = NodeId::MAX;
+
+#[derive(Clone, Copy)]
+struct ArenaNode {
+    level: u32,
+    population: u64,
+    /// nw, ne, sw, se；leaf 的子節點沒有意義
+    children: [NodeId; 4],
+}
+
+fn mix(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^ (h >> 33)
+}
+
+fn hash_children(children: &[NodeId; 4]) -> u64 {
+    let a = (children[0] as u64) << 32 | children[1] as u64;
+    let b = (children[2] as u64) << 32 | children[3] as u64;
+    mix(a ^ mix(b))
+}
+
+/// 線性探測的 hash-consing 表，槽內只存節點編號，鍵由 arena 中的子節點取得
+struct NodeTable {
+    slots: Vec<NodeId>,
+    len: usize,
+}
+
+impl NodeTable {
+    fn new() -> Self {
+        NodeTable { slots: vec![EMPTY; 1024], len: 0 }
+    }
+
+    /// 找到子節點相同的節點，或應該插入的空槽位置
+    fn probe(&self, nodes: &[ArenaNode], children: &[NodeId; 4]) -> Result<NodeId, usize> {
+        let mask = self.slots.len() - 1;
+        let mut i = hash_children(children) as usize & mask;
+        loop {
+            match self.slots[i] {
+                EMPTY => return Err(i),
+                id if nodes[id as usize].children == *children => return Ok(id),
+                _ => i = (i + 1) & mask,
+            }
+        }
+    }
+
+    fn insert_at(&mut self, nodes: &[ArenaNode], slot: usize, id: NodeId) {
+        self.slots[slot] = id;
+        self.len += 1;
+        // 負載超過一半就加倍
+        if self.len * 2 > self.slots.len() {
+            let capacity = self.slots.len() * 2;
+            let old = std::mem::replace(&mut self.slots, vec![EMPTY; capacity]);
+            let mask = self.slots.len() - 1;
+            for id in old.into_iter().filter(|&id| id != EMPTY) {
+                let mut i = hash_children(&nodes[id as usize].children) as usize & mask;
+                while self.slots[i] != EMPTY {
+                    i = (i + 1) & mask;
+                }
+                self.slots[i] = id;
+            }
+        }
+    }
+}
+
+/// 以 (節點, 步數指數) 為鍵的跳躍結果表
+struct ResultTable {
+    keys: Vec<u64>,
+    values: Vec<NodeId>,
+    len: usize,
+}
+
+impl ResultTable {
+    const EMPTY_KEY: u64 = u64::MAX;
+
+    fn new() -> Self {
+        ResultTable {
+            keys: vec![Self::EMPTY_KEY; 1024],
+            values: vec![EMPTY; 1024],
+            len: 0,
+        }
+    }
+
+    fn key(id: NodeId, j: u32) -> u64 {
+        (id as u64) << 8 | j as u64
+    }
+
+    fn get(&self, id: NodeId, j: u32) -> Option<NodeId> {
+        let key = Self::key(id, j);
+        let mask = self.keys.len() - 1;
+        let mut i = mix(key) as usize & mask;
+        loop {
+            match self.keys[i] {
+                Self::EMPTY_KEY => return None,
+                k if k == key => return Some(self.values[i]),
+                _ => i = (i + 1) & mask,
+            }
+        }
+    }
+
+    fn insert(&mut self, id: NodeId, j: u32, value: NodeId) {
+        if (self.len + 1) * 2 > self.keys.len() {
+            let capacity = self.keys.len() * 2;
+            let keys = std::mem::replace(&mut self.keys, vec![Self::EMPTY_KEY; capacity]);
+            let values = std::mem::replace(&mut self.values, vec![EMPTY; capacity]);
+            self.len = 0;
+            for (k, v) in keys.into_iter().zip(values).filter(|&(k, _)| k != Self::EMPTY_KEY) {
+                self.insert_key(k, v);
+            }
+        }
+        self.insert_key(Self::key(id, j), value);
+    }
+
+    fn insert_key(&mut self, key: u64, value: NodeId) {
+        let mask = self.keys.len() - 1;
+        let mut i = mix(key) as usize & mask;
+        while self.keys[i] != Self::EMPTY_KEY && self.keys[i] != key {
+            i = (i + 1) & mask;
+        }
+        if self.keys[i] == Self::EMPTY_KEY {
+            self.len += 1;
+        }
+        self.keys[i] = key;
+        self.values[i] = value;
+    }
+}
+
+/// 以 arena 儲存節點的 HashLife 宇宙，公開介面與 [`crate::Universe`] 相同
+pub struct ArenaUniverse {
+    nodes: Vec<ArenaNode>,
+    table: NodeTable,
+    results: ResultTable,
+    /// empties[level] 為該 level 的全空節點
+    empties: Vec<NodeId>,
+    root: NodeId,
+    generation: u64,
+    rule: Rule,
+    /// 三張表超過此大小（位元組）時，在步與步之間壓縮 arena
+    memory_budget: usize,
+    stats: Stats,
+}
+
+impl Default for ArenaUniverse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArenaUniverse {
+    const MIN_LEVEL: u32 = 3;
+
+    pub fn new() -> Self {
+        Self::with_rule(Rule::default())
+    }
+
+    pub fn with_rule(rule: Rule) -> Self {
+        let leaf = |population| ArenaNode {
+            level: 0,
+            population,
+            children: [EMPTY; 4],
+        };
+        let mut universe = ArenaUniverse {
+            nodes: vec![leaf(0), leaf(1)],
+            table: NodeTable::new(),
+            results: ResultTable::new(),
+            empties: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+            rule,
+            memory_budget: Universe::DEFAULT_MEMORY_BUDGET,
+            stats: Stats::default(),
+        };
+        universe.root = universe.empty(Self::MIN_LEVEL);
+        universe
+    }
+
+    pub fn from_cells(cells: &[(i64, i64)]) -> Result<Self, RangeError> {
+        let mut universe = Self::new();
+        for &(x, y) in cells {
+            universe.set_cell(x, y, true)?;
+        }
+        Ok(universe)
+    }
+
+    fn level(&self, id: NodeId) -> u32 {
+        self.nodes[id as usize].level
+    }
+
+    fn population_of(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    /// 建構或共用一個 level +1 的節點
+    fn node(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        self.stats.node_lookups += 1;
+        match self.table.probe(&self.nodes, &children) {
+            Ok(id) => {
+                self.stats.node_hits += 1;
+                id
+            }
+            Err(slot) => {
+                let id = NodeId::try_from(self.nodes.len())
+                    .ok()
+                    .filter(|&id| id != EMPTY)
+                    .expect("arena 節點數超過 u32 範圍");
+                let population = children.iter().map(|&c| self.population_of(c)).sum();
+                self.nodes.push(ArenaNode {
+                    level: self.level(nw) + 1,
+                    population,
+                    children,
+                });
+                self.table.insert_at(&self.nodes, slot, id);
+                id
+            }
+        }
+    }
+
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empties.len() <= level as usize {
+            let e = *self.empties.last().unwrap();
+            let next = self.node(e, e, e, e);
+            self.empties.push(next);
+        }
+        self.empties[level as usize]
+    }
+
+    fn node_cell(&self, id: NodeId, x: u64, y: u64) -> bool {
+        let node = &self.nodes[id as usize];
+        if node.level == 0 {
+            return node.population > 0;
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+        self.node_cell(node.children[quadrant], x % half, y % half)
+    }
+
+    fn with_cell(&mut self, id: NodeId, x: u64, y: u64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let mut children = self.children(id);
+        let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+        children[quadrant] = self.with_cell(children[quadrant], x % half, y % half, alive);
+        self.node(children[0], children[1], children[2], children[3])
+    }
+
+    fn compute_level2(&mut self, id: NodeId) -> NodeId {
+        let mut block = 0u16;
+        for y in 0..4 {
+            for x in 0..4 {
+                if self.node_cell(id, x, y) {
+                    block |= 1 << (y * 4 + x);
+                }
+            }
+        }
+        let [nw, ne, sw, se] = self.rule.level2(block).map(|alive| if alive { ALIVE } else { DEAD });
+        self.node(nw, ne, sw, se)
+    }
+
+    fn centered_subnode(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.node(self.children(nw)[3], self.children(ne)[2], self.children(sw)[1], self.children(se)[0])
+    }
+
+    fn centered_horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let (w, e) = (self.children(w), self.children(e));
+        self.node(w[1], e[0], w[3], e[2])
+    }
+
+    fn centered_vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let (n, s) = (self.children(n), self.children(s));
+        self.node(n[2], n[3], s[0], s[1])
+    }
+
+    /// 跳躍 2^j 步後的中心子節點，見 [`crate::Universe`] 的同名方法
+    fn advance(&mut self, id: NodeId, j: u32) -> NodeId {
+        self.stats.result_lookups += 1;
+        if let Some(cached) = self.results.get(id, j) {
+            self.stats.result_hits += 1;
+            return cached;
+        }
+        let level = self.level(id);
+        let res = if level == 2 {
+            self.compute_level2(id)
+        } else {
+            let full = j + 2 == level;
+            let [nw, ne, sw, se] = self.children(id);
+            let subs = [
+                nw,
+                self.centered_horizontal(nw, ne),
+                ne,
+                self.centered_vertical(nw, sw),
+                self.centered_subnode(id),
+                self.centered_vertical(ne, se),
+                sw,
+                self.centered_horizontal(sw, se),
+                se,
+            ];
+            let mut n = [DEAD; 9];
+            for (r, &sub) in n.iter_mut().zip(subs.iter()) {
+                *r = if full { self.advance(sub, j - 1) } else { self.centered_subnode(sub) };
+            }
+
+            let j2 = if full { j - 1 } else { j };
+            let a = self.node(n[0], n[1], n[3], n[4]);
+            let b = self.node(n[1], n[2], n[4], n[5]);
+            let c = self.node(n[3], n[4], n[6], n[7]);
+            let d = self.node(n[4], n[5], n[7], n[8]);
+            let (a, b, c, d) = (self.advance(a, j2), self.advance(b, j2), self.advance(c, j2), self.advance(d, j2));
+            self.node(a, b, c, d)
+        };
+        self.results.insert(id, j, res);
+        res
+    }
+
+    fn root_half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn expand(&mut self) -> Result<(), RangeError> {
+        check_level(self.level(self.root) + 1)?;
+        let e = self.empty(self.level(self.root) - 1);
+        let [nw, ne, sw, se] = self.children(self.root);
+        let nw = self.node(e, e, e, nw);
+        let ne = self.node(e, e, ne, e);
+        let sw = self.node(e, sw, e, e);
+        let se = self.node(se, e, e, e);
+        self.root = self.node(nw, ne, sw, se);
+        Ok(())
+    }
+
+    fn pad(&mut self, level: u32) -> Result<(), RangeError> {
+        loop {
+            let center = self.centered_subnode(self.root);
+            let inner = self.centered_subnode(center);
+            if self.level(self.root) >= level.max(Self::MIN_LEVEL)
+                && self.population_of(inner) == self.population_of(self.root)
+            {
+                break;
+            }
+            self.expand()?;
+        }
+        Ok(())
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<(), RangeError> {
+        while x < -self.root_half() || x >= self.root_half() || y < -self.root_half() || y >= self.root_half() {
+            self.expand()?;
+        }
+        let half = self.root_half();
+        self.root = self.with_cell(self.root, (x + half) as u64, (y + half) as u64, alive);
+        Ok(())
+    }
+
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        let half = self.root_half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+        self.node_cell(self.root, (x + half) as u64, (y + half) as u64)
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.population_of(self.root)
+    }
+
+    /// arena 中的節點數（不含兩個 leaf）
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - 2
+    }
+
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.cells().into_iter().fold(None, |bounds, (x, y)| {
+            Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            })
+        })
+    }
+
+    pub fn cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::with_capacity(self.population() as usize);
+        let half = self.root_half();
+        let mut stack = vec![(self.root, -half, -half)];
+        while let Some((id, x, y)) = stack.pop() {
+            let node = &self.nodes[id as usize];
+            if node.population == 0 {
+                continue;
+            }
+            if node.level == 0 {
+                cells.push((x, y));
+                continue;
+            }
+            let half = 1 << (node.level - 1);
+            let [nw, ne, sw, se] = node.children;
+            stack.extend([(nw, x, y), (ne, x + half, y), (sw, x, y + half), (se, x + half, y + half)]);
+        }
+        cells
+    }
+
+    /// 前進 `generations` 個世代，拆成 2 的冪次逐一跳躍
+    pub fn step(&mut self, generations: u64) -> Result<(), RangeError> {
+        check_step(self.generation, generations)?;
+        for j in 0..u64::BITS {
+            if generations & (1 << j) != 0 {
+                self.pad(j + 2)?;
+                self.root = self.advance(self.root, j);
+                self.generation += 1 << j;
+                if self.memory_usage() > self.memory_budget {
+                    self.collect_garbage();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 設定 arena 與兩張雜湊表的記憶體預算（位元組）
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = bytes;
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.nodes.capacity() * mem::size_of::<ArenaNode>()
+            + self.table.slots.len() * mem::size_of::<NodeId>()
+            + self.results.keys.len() * (mem::size_of::<u64>() + mem::size_of::<NodeId>())
+    }
+
+    /// 目前的快取統計
+    pub fn stats(&self) -> Stats {
+        Stats {
+            nodes: self.node_count(),
+            memory_bytes: self.memory_usage(),
+            ..self.stats
+        }
+    }
+
+    /// 把全空節點與根節點可到達的節點依後序複製到新的 arena，其餘節點連同編號一起釋放
+    ///
+    /// 兩端都留下來的跳躍結果換成新編號保留，其餘結果丟棄。
+    pub fn collect_garbage(&mut self) {
+        let mut remap = vec![EMPTY; self.nodes.len()];
+        remap[DEAD as usize] = DEAD;
+        remap[ALIVE as usize] = ALIVE;
+        let mut nodes = vec![self.nodes[DEAD as usize], self.nodes[ALIVE as usize]];
+        for id in self.empties.iter().copied().chain([self.root]) {
+            self.copy_reachable(id, &mut remap, &mut nodes);
+        }
+
+        let mut table = NodeTable::new();
+        for id in 2..nodes.len() {
+            if let Err(slot) = table.probe(&nodes, &nodes[id].children) {
+                table.insert_at(&nodes, slot, id as NodeId);
+            }
+        }
+        let mut results = ResultTable::new();
+        let old = mem::replace(&mut self.results, ResultTable::new());
+        for (key, value) in old.keys.into_iter().zip(old.values).filter(|&(k, _)| k != ResultTable::EMPTY_KEY) {
+            let (id, j) = ((key >> 8) as usize, (key & 0xff) as u32);
+            if remap[id] != EMPTY && remap[value as usize] != EMPTY {
+                results.insert(remap[id], j, remap[value as usize]);
+            }
+        }
+
+        for id in self.empties.iter_mut() {
+            *id = remap[*id as usize];
+        }
+        self.root = remap[self.root as usize];
+        self.nodes = nodes;
+        self.table = table;
+        self.results = results;
+        self.stats.collections += 1;
+    }
+
+    fn copy_reachable(&self, id: NodeId, remap: &mut [NodeId], nodes: &mut Vec<ArenaNode>) -> NodeId {
+        if remap[id as usize] != EMPTY {
+            return remap[id as usize];
+        }
+        let mut node = self.nodes[id as usize];
+        for child in node.children.iter_mut() {
+            *child = self.copy_reachable(*child, remap, nodes);
+        }
+        let new_id = nodes.len() as NodeId;
+        nodes.push(node);
+        remap[id as usize] = new_id;
+        new_id
+    }
+
+    /// 讀取 macrocell 格式，沿用 [`Universe::read_macrocell`] 的解析再轉成 arena 節點
+    pub fn read_macrocell(reader: impl BufRead) -> Result<ArenaUniverse, MacrocellError> {
+        let universe = Universe::read_macrocell(reader)?;
+        let mut arena = ArenaUniverse::with_rule(universe.rule.clone());
+        let mut ids = HashMap::new();
+        arena.root = arena.import(&universe.root, &mut ids);
+        arena.generation = universe.generation;
+        Ok(arena)
+    }
+
+    fn import(&mut self, node: &Node, ids: &mut HashMap<*const Node, NodeId>) -> NodeId {
+        if node.level == 0 {
+            return if node.population > 0 { ALIVE } else { DEAD };
+        }
+        if let Some(&id) = ids.get(&(node as *const Node)) {
+            return id;
+        }
+        let children = [node.nw(), node.ne(), node.sw(), node.se()].map(|child| self.import(child, ids));
+        let id = self.node(children[0], children[1], children[2], children[3]);
+        ids.insert(node as *const Node, id);
+        id
+    }
+
+    /// 以 macrocell 格式寫出，先轉成共用節點的 [`Universe`] 再沿用其寫出程式
+    pub fn write_macrocell(&self, writer: impl Write) -> io::Result<()> {
+        let mut universe = Universe::with_rule(self.rule.clone());
+        let mut nodes = HashMap::new();
+        universe.root = self.export(self.root, &universe, &mut nodes);
+        universe.generation = self.generation;
+        universe.write_macrocell(writer)
+    }
+
+    fn export(&self, id: NodeId, universe: &Universe, nodes: &mut HashMap<NodeId, Rc<Node>>) -> Rc<Node> {
+        if self.level(id) == 0 {
+            return universe.leaf(id == ALIVE);
+        }
+        if let Some(node) = nodes.get(&id) {
+            return node.clone();
+        }
+        let [nw, ne, sw, se] = self.children(id).map(|child| self.export(child, universe, nodes));
+        let node = universe.node(nw, ne, sw, se);
+        nodes.insert(id, node.clone());
+        node
+    }
+}
@@ -0,0 +1,567 @@
+//! HashLife：以 hash-consing 四叉樹與結果快取模擬康威生命遊戲及其他生命遊戲類規則
+//!
+//! 座標系以原點為中心，x 向右、y 向下遞增。
+
+mod arena;
+mod macrocell;
+mod parallel;
+mod rle;
+mod rule;
+
+pub use arena::ArenaUniverse;
+pub use macrocell::MacrocellError;
+pub use parallel::{ParallelNode, ParallelUniverse};
+pub use rle::{RleError, RlePattern};
+pub use rule::{Rule, RuleError};
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// 四叉樹節點，level 表示區域邊長為 2^level
+#[derive(Debug)]
+struct Node {
+    level: u32,
+    population: u64,
+    nw: Option<Rc<Node>>,
+    ne: Option<Rc<Node>>,
+    sw: Option<Rc<Node>>,
+    se: Option<Rc<Node>>,
+    /// 快取此節點跳躍 2^j 步後的中心子節點，以 j 為索引（0 <= j <= level-2）
+    results: RefCell<Vec<Option<Rc<Node>>>>,
+}
+
+impl Node {
+    fn nw(&self) -> &Rc<Node> {
+        self.nw.as_ref().unwrap()
+    }
+
+    fn ne(&self) -> &Rc<Node> {
+        self.ne.as_ref().unwrap()
+    }
+
+    fn sw(&self) -> &Rc<Node> {
+        self.sw.as_ref().unwrap()
+    }
+
+    fn se(&self) -> &Rc<Node> {
+        self.se.as_ref().unwrap()
+    }
+}
+
+/// 用原始指標與 level 作為雜湊鍵，實現 hash-consing
+///
+/// 鍵中的子節點都是快取內存活的節點或 leaf；垃圾回收只移除無法到達的節點，
+/// 其子節點的指標不會出現在任何留下來的鍵裡，因此位址被重用也不會誤判。
+#[derive(Eq, PartialEq)]
+struct NodeKey {
+    level: u32,
+    nw: *const Node,
+    ne: *const Node,
+    sw: *const Node,
+    se: *const Node,
+}
+impl Hash for NodeKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.level.hash(state);
+        (self.nw as usize).hash(state);
+        (self.ne as usize).hash(state);
+        (self.sw as usize).hash(state);
+        (self.se as usize).hash(state);
+    }
+}
+
+/// 節點快取的統計資訊
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    /// 快取中的節點數
+    pub nodes: usize,
+    /// 快取佔用記憶體的估計值（位元組）
+    pub memory_bytes: usize,
+    pub node_lookups: u64,
+    pub node_hits: u64,
+    pub result_lookups: u64,
+    pub result_hits: u64,
+    /// 已執行的垃圾回收次數
+    pub collections: u64,
+}
+
+impl Stats {
+    /// 跳躍結果快取的命中率
+    pub fn hit_rate(&self) -> f64 {
+        if self.result_lookups == 0 {
+            0.0
+        } else {
+            self.result_hits as f64 / self.result_lookups as f64
+        }
+    }
+
+    /// hash-consing 查表的命中率
+    pub fn node_hit_rate(&self) -> f64 {
+        if self.node_lookups == 0 {
+            0.0
+        } else {
+            self.node_hits as f64 / self.node_lookups as f64
+        }
+    }
+}
+
+/// 細胞座標或跳躍步數超出根節點 level 上限 [`Universe::MAX_LEVEL`] 能表示的範圍
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeError;
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pattern exceeds the coordinate range of a level {} root", Universe::MAX_LEVEL)
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+/// 根節點能否擴大到 `level`；三種宇宙共用同一個上限
+pub(crate) fn check_level(level: u32) -> Result<(), RangeError> {
+    if level <= Universe::MAX_LEVEL { Ok(()) } else { Err(RangeError) }
+}
+
+/// 從第 `generation` 代前進 `generations` 代時，最大的跳躍與累計世代數是否都在範圍內
+pub(crate) fn check_step(generation: u64, generations: u64) -> Result<(), RangeError> {
+    check_level((u64::BITS - generations.leading_zeros()) + 1)?;
+    generation.checked_add(generations).map(|_| ()).ok_or(RangeError)
+}
+
+/// 唯讀的節點參照，讓其他程式走訪四叉樹，例如繪製或轉換成自己的節點
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a>(&'a Node);
+
+impl<'a> NodeRef<'a> {
+    /// 節點邊長為 2^level
+    pub fn level(self) -> u32 {
+        self.0.level
+    }
+
+    pub fn population(self) -> u64 {
+        self.0.population
+    }
+
+    /// 依 nw、ne、sw、se 順序的子節點；level 0 的細胞沒有子節點
+    pub fn children(self) -> Option<[NodeRef<'a>; 4]> {
+        match (&self.0.nw, &self.0.ne, &self.0.sw, &self.0.se) {
+            (Some(nw), Some(ne), Some(sw), Some(se)) => Some([NodeRef(nw), NodeRef(ne), NodeRef(sw), NodeRef(se)]),
+            _ => None,
+        }
+    }
+
+    /// 節點的識別碼；hash-consing 保證內容相同的節點識別碼相同，可用來記住已轉換過的節點
+    pub fn id(self) -> usize {
+        self.0 as *const Node as usize
+    }
+}
+
+/// Hashlife 主體，保存節點快取與以原點為中心的根節點
+pub struct Universe {
+    cache: RefCell<HashMap<NodeKey, Rc<Node>>>,
+    leaf0: Rc<Node>,
+    leaf1: Rc<Node>,
+    root: Rc<Node>,
+    generation: u64,
+    rule: Rule,
+    /// 快取超過此大小（位元組）時，在步與步之間回收無法從根節點到達的節點
+    memory_budget: usize,
+    stats: RefCell<Stats>,
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Universe {
+    /// 根節點的最小 level
+    const MIN_LEVEL: u32 = 3;
+    /// 節點的最大 level：根節點的半邊長 2^(level-1) 與跳躍步數 2^(level-2) 都要放得進 i64 與 u64
+    pub const MAX_LEVEL: u32 = 62;
+    /// 預設的記憶體預算
+    pub const DEFAULT_MEMORY_BUDGET: usize = 512 << 20;
+    /// 每個快取節點約佔用的位元組：節點本體與 Rc 計數、雜湊鍵、表中的 Rc 與控制位元組
+    const NODE_BYTES: usize = std::mem::size_of::<Node>()
+        + 2 * std::mem::size_of::<usize>()
+        + std::mem::size_of::<NodeKey>()
+        + std::mem::size_of::<Rc<Node>>()
+        + 1;
+
+    /// 以康威生命遊戲 B3/S23 建立空白宇宙
+    pub fn new() -> Self {
+        Self::with_rule(Rule::default())
+    }
+
+    /// 初始化兩個 leaf 節點、快取與空白的根節點
+    pub fn with_rule(rule: Rule) -> Self {
+        let leaf0 = Rc::new(Node {
+            level: 0,
+            population: 0,
+            nw: None, ne: None, sw: None, se: None,
+            results: RefCell::new(Vec::new()),
+        });
+        let leaf1 = Rc::new(Node {
+            level: 0,
+            population: 1,
+            nw: None, ne: None, sw: None, se: None,
+            results: RefCell::new(Vec::new()),
+        });
+        let mut universe = Universe {
+            cache: RefCell::new(HashMap::new()),
+            root: leaf0.clone(),
+            leaf0,
+            leaf1,
+            generation: 0,
+            rule,
+            memory_budget: Self::DEFAULT_MEMORY_BUDGET,
+            stats: RefCell::new(Stats::default()),
+        };
+        universe.root = universe.empty(Self::MIN_LEVEL);
+        universe
+    }
+
+    /// 由活細胞座標建立宇宙
+    pub fn from_cells(cells: &[(i64, i64)]) -> Result<Self, RangeError> {
+        let mut universe = Self::new();
+        for &(x, y) in cells {
+            universe.set_cell(x, y, true)?;
+        }
+        Ok(universe)
+    }
+
+    /// 取得 leaf 節點
+    fn leaf(&self, alive: bool) -> Rc<Node> {
+        if alive { self.leaf1.clone() } else { self.leaf0.clone() }
+    }
+
+    /// 建構或共用一個 level +1 的四叉樹節點
+    fn node(&self, nw: Rc<Node>, ne: Rc<Node>, sw: Rc<Node>, se: Rc<Node>) -> Rc<Node> {
+        let level = nw.level + 1;
+        let population = nw.population + ne.population + sw.population + se.population;
+        let key = NodeKey {
+            level,
+            nw: Rc::as_ptr(&nw),
+            ne: Rc::as_ptr(&ne),
+            sw: Rc::as_ptr(&sw),
+            se: Rc::as_ptr(&se),
+        };
+        self.stats.borrow_mut().node_lookups += 1;
+        if let Some(existing) = self.cache.borrow().get(&key) {
+            self.stats.borrow_mut().node_hits += 1;
+            return existing.clone();
+        }
+        let new_node = Rc::new(Node {
+            level,
+            population,
+            nw: Some(nw.clone()),
+            ne: Some(ne.clone()),
+            sw: Some(sw.clone()),
+            se: Some(se.clone()),
+            results: RefCell::new(Vec::new()),
+        });
+        self.cache.borrow_mut().insert(key, new_node.clone());
+        new_node
+    }
+
+    /// 取得指定 level 的全空節點
+    fn empty(&self, level: u32) -> Rc<Node> {
+        let mut node = self.leaf(false);
+        for _ in 0..level {
+            node = self.node(node.clone(), node.clone(), node.clone(), node);
+        }
+        node
+    }
+
+    /// 讀取節點中 (x,y) 處的生死，x,y 範圍為 [0, 2^level)
+    fn node_cell(&self, node: &Rc<Node>, x: usize, y: usize) -> bool {
+        if node.level == 0 {
+            return node.population > 0;
+        }
+        let half = 1 << (node.level - 1);
+        if x < half {
+            if y < half {
+                self.node_cell(node.nw(), x, y)
+            } else {
+                self.node_cell(node.sw(), x, y - half)
+            }
+        } else if y < half {
+            self.node_cell(node.ne(), x - half, y)
+        } else {
+            self.node_cell(node.se(), x - half, y - half)
+        }
+    }
+
+    /// 回傳設定 (x,y) 後的新節點，x,y 範圍為 [0, 2^level)
+    fn with_cell(&self, node: &Rc<Node>, x: u64, y: u64, alive: bool) -> Rc<Node> {
+        if node.level == 0 {
+            return self.leaf(alive);
+        }
+        let half = 1 << (node.level - 1);
+        let (mut nw, mut ne, mut sw, mut se) =
+            (node.nw().clone(), node.ne().clone(), node.sw().clone(), node.se().clone());
+        match (x < half, y < half) {
+            (true, true) => nw = self.with_cell(&nw, x, y, alive),
+            (false, true) => ne = self.with_cell(&ne, x - half, y, alive),
+            (true, false) => sw = self.with_cell(&sw, x, y - half, alive),
+            (false, false) => se = self.with_cell(&se, x - half, y - half, alive),
+        }
+        self.node(nw, ne, sw, se)
+    }
+
+    /// 基底：以規則的查表讓 level=2 的 4x4 區塊前進 1 步，回傳中心 2x2 節點
+    fn compute_level2(&self, node: &Rc<Node>) -> Rc<Node> {
+        let mut block = 0u16;
+        for y in 0..4 {
+            for x in 0..4 {
+                if self.node_cell(node, x, y) {
+                    block |= 1 << (y * 4 + x);
+                }
+            }
+        }
+        let [nw, ne, sw, se] = self.rule.level2(block);
+        self.node(self.leaf(nw), self.leaf(ne), self.leaf(sw), self.leaf(se))
+    }
+
+    /// 提取中心子節點（level-1）用於遞歸
+    fn centered_subnode(&self, node: &Rc<Node>) -> Rc<Node> {
+        self.node(
+            node.nw().se().clone(),
+            node.ne().sw().clone(),
+            node.sw().ne().clone(),
+            node.se().nw().clone(),
+        )
+    }
+
+    /// 取得橫跨左右相鄰兩節點交界、與其同 level 的節點
+    fn centered_horizontal(&self, w: &Rc<Node>, e: &Rc<Node>) -> Rc<Node> {
+        self.node(w.ne().clone(), e.nw().clone(), w.se().clone(), e.sw().clone())
+    }
+
+    /// 取得橫跨上下相鄰兩節點交界、與其同 level 的節點
+    fn centered_vertical(&self, n: &Rc<Node>, s: &Rc<Node>) -> Rc<Node> {
+        self.node(n.sw().clone(), n.se().clone(), s.nw().clone(), s.ne().clone())
+    }
+
+    /// 遞歸計算 hash life：跳躍 2^j 步後的中心子節點（level-1），需 j <= level-2
+    ///
+    /// 先把節點拆成九個互相重疊的 level-1 子節點，再把相鄰的 2x2 組成四個 level-1 節點
+    /// 各自遞歸。j = level-2 時兩個階段各跳躍 2^(j-1) 步；較小的 j 則第一階段只取中心、
+    /// 不前進，全部 2^j 步都交給第二階段。
+    fn advance(&self, node: &Rc<Node>, j: u32) -> Rc<Node> {
+        debug_assert!(j + 2 <= node.level);
+        self.stats.borrow_mut().result_lookups += 1;
+        if let Some(Some(cached)) = node.results.borrow().get(j as usize) {
+            self.stats.borrow_mut().result_hits += 1;
+            return cached.clone();
+        }
+        let res = if node.level == 2 {
+            self.compute_level2(node)
+        } else {
+            let full = j + 2 == node.level;
+            let phase1 = |sub: &Rc<Node>| {
+                if full {
+                    self.advance(sub, j - 1)
+                } else {
+                    self.centered_subnode(sub)
+                }
+            };
+            let n00 = phase1(node.nw());
+            let n01 = phase1(&self.centered_horizontal(node.nw(), node.ne()));
+            let n02 = phase1(node.ne());
+            let n10 = phase1(&self.centered_vertical(node.nw(), node.sw()));
+            let n11 = phase1(&self.centered_subnode(node));
+            let n12 = phase1(&self.centered_vertical(node.ne(), node.se()));
+            let n20 = phase1(node.sw());
+            let n21 = phase1(&self.centered_horizontal(node.sw(), node.se()));
+            let n22 = phase1(node.se());
+
+            let j2 = if full { j - 1 } else { j };
+            self.node(
+                self.advance(&self.node(n00, n01.clone(), n10.clone(), n11.clone()), j2),
+                self.advance(&self.node(n01, n02, n11.clone(), n12.clone()), j2),
+                self.advance(&self.node(n10, n11.clone(), n20, n21.clone()), j2),
+                self.advance(&self.node(n11, n12, n21, n22), j2),
+            )
+        };
+        let mut results = node.results.borrow_mut();
+        if results.len() <= j as usize {
+            results.resize(j as usize + 1, None);
+        }
+        results[j as usize] = Some(res.clone());
+        res
+    }
+
+    /// 根節點覆蓋 [-half, half) 的範圍
+    fn root_half(&self) -> i64 {
+        1 << (self.root.level - 1)
+    }
+
+    /// 在外圍補一圈空白，根節點 level +1 且內容保持在原位
+    fn expand(&mut self) -> Result<(), RangeError> {
+        check_level(self.root.level + 1)?;
+        let empty = self.empty(self.root.level - 1);
+        let root = self.root.clone();
+        self.root = self.node(
+            self.node(empty.clone(), empty.clone(), empty.clone(), root.nw().clone()),
+            self.node(empty.clone(), empty.clone(), root.ne().clone(), empty.clone()),
+            self.node(empty.clone(), root.sw().clone(), empty.clone(), empty.clone()),
+            self.node(root.se().clone(), empty.clone(), empty.clone(), empty),
+        );
+        Ok(())
+    }
+
+    /// 擴大根節點，直到 level >= `level` 且所有活細胞都落在中心 1/4 寬的區域內，
+    /// 確保跳躍後不會有細胞離開結果範圍
+    fn pad(&mut self, level: u32) -> Result<(), RangeError> {
+        while self.root.level < level.max(Self::MIN_LEVEL)
+            || self.centered_subnode(&self.centered_subnode(&self.root)).population != self.root.population
+        {
+            self.expand()?;
+        }
+        Ok(())
+    }
+
+    /// 設定 (x,y) 的生死，必要時擴大宇宙；座標超出範圍時宇宙內容不變
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<(), RangeError> {
+        while x < -self.root_half() || x >= self.root_half() || y < -self.root_half() || y >= self.root_half() {
+            self.expand()?;
+        }
+        let half = self.root_half();
+        self.root = self.with_cell(&self.root, (x + half) as u64, (y + half) as u64, alive);
+        Ok(())
+    }
+
+    /// 讀取 (x,y) 的生死
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        let half = self.root_half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+        self.node_cell(&self.root, (x + half) as usize, (y + half) as usize)
+    }
+
+    /// 以原點為中心的根節點
+    pub fn root(&self) -> NodeRef<'_> {
+        NodeRef(&self.root)
+    }
+
+    /// 演化所用的規則
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// 目前已經過的世代數
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 活細胞數量
+    pub fn population(&self) -> u64 {
+        self.root.population
+    }
+
+    /// 活細胞的邊界框 (min_x, min_y, max_x, max_y)，皆為閉區間；空宇宙回傳 None
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        let half = self.root_half();
+        Self::visit_alive(&self.root, -half, -half, &mut |x, y| {
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            });
+        });
+        bounds
+    }
+
+    /// 所有活細胞的座標
+    pub fn cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::with_capacity(self.population() as usize);
+        let half = self.root_half();
+        Self::visit_alive(&self.root, -half, -half, &mut |x, y| cells.push((x, y)));
+        cells
+    }
+
+    /// 走訪節點內的活細胞，(x,y) 為節點左上角的座標，略過全空的子樹
+    fn visit_alive(node: &Rc<Node>, x: i64, y: i64, f: &mut impl FnMut(i64, i64)) {
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            f(x, y);
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        Self::visit_alive(node.nw(), x, y, f);
+        Self::visit_alive(node.ne(), x + half, y, f);
+        Self::visit_alive(node.sw(), x, y + half, f);
+        Self::visit_alive(node.se(), x + half, y + half, f);
+    }
+
+    /// 前進 `generations` 個世代
+    ///
+    /// 把步數拆成 2 的冪次，每個冪次各做一次有快取的跳躍。跳躍太大時不做任何事就回傳錯誤；
+    /// 圖樣途中長出範圍時停在已完成的世代，`generation()` 與內容一致。
+    pub fn step(&mut self, generations: u64) -> Result<(), RangeError> {
+        check_step(self.generation, generations)?;
+        for j in 0..u64::BITS {
+            if generations & (1 << j) != 0 {
+                self.pad(j + 2)?;
+                self.root = self.advance(&self.root, j);
+                self.generation += 1 << j;
+                if self.memory_usage() > self.memory_budget {
+                    self.collect_garbage();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 設定節點快取的記憶體預算（位元組）
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = bytes;
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cache.borrow().len() * Self::NODE_BYTES
+    }
+
+    /// 目前的快取統計
+    pub fn stats(&self) -> Stats {
+        Stats {
+            nodes: self.cache.borrow().len(),
+            memory_bytes: self.memory_usage(),
+            ..*self.stats.borrow()
+        }
+    }
+
+    /// 標記並清除：保留從根節點經由子節點可到達的節點，其餘移出快取
+    ///
+    /// 存活節點的跳躍結果若指向被移除的節點也一併丟棄，否則該節點會在快取外存活，
+    /// 之後再建立相同內容時就不再是唯一的。
+    pub fn collect_garbage(&mut self) {
+        let mut marked: HashSet<*const Node> = HashSet::new();
+        let mut stack = vec![self.root.clone()];
+        while let Some(node) = stack.pop() {
+            if node.level == 0 || !marked.insert(Rc::as_ptr(&node)) {
+                continue;
+            }
+            stack.extend([node.nw().clone(), node.ne().clone(), node.sw().clone(), node.se().clone()]);
+        }
+
+        let mut cache = self.cache.borrow_mut();
+        cache.retain(|_, node| marked.contains(&Rc::as_ptr(node)));
+        for node in cache.values() {
+            for result in node.results.borrow_mut().iter_mut() {
+                if result.as_ref().is_some_and(|r| r.level > 0 && !marked.contains(&Rc::as_ptr(r))) {
+                    *result = None;
+                }
+            }
+        }
+        self.stats.borrow_mut().collections += 1;
+    }
+}
@@ -0,0 +1,185 @@
+//! Golly 的 macrocell（.mc）格式
+//!
+//! 檔案直接描述 hash-consed 四叉樹：level 3（8x8）的節點寫成一行以 `.`、`*`、`$` 表示的點陣，
+//! 更高的節點寫成 `level nw ne sw se`，子節點以 1 起算的行號參照，0 代表全空節點，
+//! 最後一個節點為根節點。根節點以原點為中心，與 [`Universe`] 的座標系相同。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::{Node, Rule, Universe};
+
+/// 讀取 macrocell 檔案時的錯誤
+#[derive(Debug)]
+pub enum MacrocellError {
+    Io(io::Error),
+    /// 第幾行（從 1 起算）與原因
+    Parse(usize, String),
+}
+
+impl fmt::Display for MacrocellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MacrocellError::Io(e) => write!(f, "{}", e),
+            MacrocellError::Parse(line, message) => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for MacrocellError {}
+
+impl From<io::Error> for MacrocellError {
+    fn from(e: io::Error) -> Self {
+        MacrocellError::Io(e)
+    }
+}
+
+/// 點陣節點的 level
+const LEAF_LEVEL: u32 = 3;
+
+impl Universe {
+    /// 讀取 macrocell 格式，包含規則（`#R`）與世代數（`#G`）
+    pub fn read_macrocell(reader: impl BufRead) -> Result<Universe, MacrocellError> {
+        let mut universe = Universe::new();
+        // nodes[i] 為第 i 個節點（從 1 起算），nodes[0] 不使用
+        let mut nodes: Vec<Rc<Node>> = vec![universe.leaf(false)];
+        let mut seen_header = false;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            let line_no = i + 1;
+            let error = |message: String| MacrocellError::Parse(line_no, message);
+
+            if !seen_header {
+                if !line.starts_with("[M2]") {
+                    return Err(error("missing [M2] header".to_owned()));
+                }
+                seen_header = true;
+            } else if let Some(rule) = line.strip_prefix("#R") {
+                // 節點屬於建立它的宇宙，規則必須在節點之前
+                if nodes.len() > 1 {
+                    return Err(error("#R after node lines".to_owned()));
+                }
+                let rule = Rule::parse(rule).map_err(|e| error(e.to_string()))?;
+                let generation = universe.generation;
+                universe = Universe::with_rule(rule);
+                universe.generation = generation;
+                nodes = vec![universe.leaf(false)];
+            } else if let Some(generation) = line.strip_prefix("#G") {
+                universe.generation = generation
+                    .trim()
+                    .parse()
+                    .map_err(|e| error(format!("invalid generation: {}", e)))?;
+            } else if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+                let fields: Vec<u64> = line
+                    .split_whitespace()
+                    .map(|f| f.parse::<u64>().map_err(|e| error(format!("{}: {}", f, e))))
+                    .collect::<Result<_, _>>()?;
+                let &[level, nw, ne, sw, se] = fields.as_slice() else {
+                    return Err(error("expected `level nw ne sw se`".to_owned()));
+                };
+                if level > Universe::MAX_LEVEL as u64 {
+                    return Err(error(format!("node level {} exceeds the maximum of {}", level, Universe::MAX_LEVEL)));
+                }
+                let level = level as u32;
+                if level <= LEAF_LEVEL {
+                    return Err(error(format!("node level {} should be written as a bitmap", level)));
+                }
+                let child = |index: u64| -> Result<Rc<Node>, MacrocellError> {
+                    if index == 0 {
+                        return Ok(universe.empty(level - 1));
+                    }
+                    let node = nodes
+                        .get(index as usize)
+                        .ok_or_else(|| error(format!("reference to undefined node {}", index)))?;
+                    if node.level != level - 1 {
+                        return Err(error(format!("node {} has level {}, expected {}", index, node.level, level - 1)));
+                    }
+                    Ok(node.clone())
+                };
+                let children = (child(nw)?, child(ne)?, child(sw)?, child(se)?);
+                nodes.push(universe.node(children.0, children.1, children.2, children.3));
+            } else {
+                let mut node = universe.empty(LEAF_LEVEL);
+                let (mut x, mut y) = (0u64, 0u64);
+                for c in line.chars() {
+                    match c {
+                        '.' => x += 1,
+                        '*' => {
+                            if x >= 8 || y >= 8 {
+                                return Err(error("bitmap exceeds 8x8".to_owned()));
+                            }
+                            node = universe.with_cell(&node, x, y, true);
+                            x += 1;
+                        }
+                        '$' => {
+                            x = 0;
+                            y += 1;
+                        }
+                        c => return Err(error(format!("unexpected character '{}'", c))),
+                    }
+                }
+                nodes.push(node);
+            }
+        }
+
+        // 只有標頭的檔案代表空宇宙
+        if nodes.len() > 1 {
+            universe.root = nodes.pop().unwrap();
+        }
+        Ok(universe)
+    }
+
+    /// 以 macrocell 格式寫出目前的宇宙，不需要展開成細胞列表
+    pub fn write_macrocell(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "[M2] (hashlife_br {})", env!("CARGO_PKG_VERSION"))?;
+        writeln!(writer, "#R {}", self.rule)?;
+        if self.generation > 0 {
+            writeln!(writer, "#G {}", self.generation)?;
+        }
+        let mut indices = HashMap::new();
+        self.write_node(&self.root, &mut writer, &mut indices)?;
+        Ok(())
+    }
+
+    /// 後序寫出節點並回傳其行號；全空節點以 0 表示
+    fn write_node(
+        &self,
+        node: &Rc<Node>,
+        writer: &mut impl Write,
+        indices: &mut HashMap<*const Node, usize>,
+    ) -> io::Result<usize> {
+        if node.population == 0 {
+            return Ok(0);
+        }
+        if let Some(&index) = indices.get(&Rc::as_ptr(node)) {
+            return Ok(index);
+        }
+        if node.level == LEAF_LEVEL {
+            // 每列去掉結尾的死細胞並以 $ 結束，最後的空白列省略
+            let rows: Vec<String> = (0..8)
+                .map(|y| {
+                    let row: String = (0..8).map(|x| if self.node_cell(node, x, y) { '*' } else { '.' }).collect();
+                    row.trim_end_matches('.').to_owned()
+                })
+                .collect();
+            let last = rows.iter().rposition(|row| !row.is_empty()).unwrap_or(0);
+            let line: String = rows[..=last].iter().map(|row| format!("{}$", row)).collect();
+            writeln!(writer, "{}", line)?;
+        } else {
+            let nw = self.write_node(node.nw(), writer, indices)?;
+            let ne = self.write_node(node.ne(), writer, indices)?;
+            let sw = self.write_node(node.sw(), writer, indices)?;
+            let se = self.write_node(node.se(), writer, indices)?;
+            writeln!(writer, "{} {} {} {} {}", node.level, nw, ne, sw, se)?;
+        }
+        let index = indices.len() + 1;
+        indices.insert(Rc::as_ptr(node), index);
+        Ok(index)
+    }
+}
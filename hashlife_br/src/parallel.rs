@@ -0,0 +1,420 @@
+//! 可跨執行緒共用的 HashLife，遞歸中互相獨立的子結果以 rayon 平行計算
+//!
+//! 節點以 `Arc` 共享，hash-consing 表與跳躍結果表使用 `DashMap`。
+//! 同一個節點可能被兩個執行緒同時建立，但表的 entry 操作保證只有一份留下，
+//! 而結果只取決於節點內容，所以輸出與單執行緒版本完全相同。
+//! 兩張表超過記憶體預算時，在步與步之間移除無法從根節點到達的節點。
+
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use rayon::prelude::*;
+
+use crate::{check_level, check_step, MacrocellError, Node, RangeError, Rule, Universe};
+
+/// 不可變的節點；以 `Arc` 共享，可以交給其他執行緒走訪，例如在 UI 執行緒繪製最新的根節點
+pub struct ParallelNode {
+    /// 唯一編號，作為雜湊鍵；leaf 為 0 與 1
+    id: u64,
+    level: u32,
+    population: u64,
+    children: Option<[Arc<ParallelNode>; 4]>,
+}
+
+impl ParallelNode {
+    /// 節點邊長為 2^level
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn population(&self) -> u64 {
+        self.population
+    }
+
+    /// 依 nw、ne、sw、se 順序的子節點；level 0 的細胞沒有子節點
+    pub fn children(&self) -> Option<&[Arc<ParallelNode>; 4]> {
+        self.children.as_ref()
+    }
+
+    fn quadrants(&self) -> &[Arc<ParallelNode>; 4] {
+        self.children.as_ref().unwrap()
+    }
+}
+
+/// level 低於此值的節點在目前執行緒內遞歸，避免排程成本大於計算本身
+const PARALLEL_LEVEL: u32 = 8;
+
+pub struct ParallelUniverse {
+    table: DashMap<[u64; 4], Arc<ParallelNode>>,
+    results: DashMap<(u64, u32), Arc<ParallelNode>>,
+    next_id: AtomicU64,
+    leaves: [Arc<ParallelNode>; 2],
+    root: Arc<ParallelNode>,
+    generation: u64,
+    rule: Rule,
+    /// 表超過此大小（位元組）時，在步與步之間回收無法從根節點到達的節點
+    memory_budget: usize,
+    collections: u64,
+}
+
+impl Default for ParallelUniverse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParallelUniverse {
+    const MIN_LEVEL: u32 = 3;
+    /// 每個節點約佔用的位元組：節點本體與 Arc 計數、鍵與表中的 Arc，另加上控制位元組
+    const NODE_BYTES: usize = std::mem::size_of::<ParallelNode>()
+        + 2 * std::mem::size_of::<usize>()
+        + std::mem::size_of::<[u64; 4]>()
+        + std::mem::size_of::<Arc<ParallelNode>>()
+        + 1;
+    /// 每筆跳躍結果約佔用的位元組
+    const RESULT_BYTES: usize = std::mem::size_of::<(u64, u32)>() + std::mem::size_of::<Arc<ParallelNode>>() + 1;
+
+    pub fn new() -> Self {
+        Self::with_rule(Rule::default())
+    }
+
+    pub fn with_rule(rule: Rule) -> Self {
+        let leaf = |id| {
+            Arc::new(ParallelNode {
+                id,
+                level: 0,
+                population: id,
+                children: None,
+            })
+        };
+        let leaves = [leaf(0), leaf(1)];
+        let mut universe = ParallelUniverse {
+            table: DashMap::new(),
+            results: DashMap::new(),
+            next_id: AtomicU64::new(2),
+            root: leaves[0].clone(),
+            leaves,
+            generation: 0,
+            rule,
+            memory_budget: Universe::DEFAULT_MEMORY_BUDGET,
+            collections: 0,
+        };
+        universe.root = universe.empty(Self::MIN_LEVEL);
+        universe
+    }
+
+    pub fn from_cells(cells: &[(i64, i64)]) -> Result<Self, RangeError> {
+        let mut universe = Self::new();
+        for &(x, y) in cells {
+            universe.set_cell(x, y, true)?;
+        }
+        Ok(universe)
+    }
+
+    fn leaf(&self, alive: bool) -> Arc<ParallelNode> {
+        self.leaves[alive as usize].clone()
+    }
+
+    /// 建構或共用一個 level +1 的節點，可由多個執行緒同時呼叫
+    fn node(&self, children: [Arc<ParallelNode>; 4]) -> Arc<ParallelNode> {
+        let key = [children[0].id, children[1].id, children[2].id, children[3].id];
+        if let Some(existing) = self.table.get(&key) {
+            return existing.clone();
+        }
+        self.table
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(ParallelNode {
+                    id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                    level: children[0].level + 1,
+                    population: children.iter().map(|c| c.population).sum(),
+                    children: Some(children),
+                })
+            })
+            .clone()
+    }
+
+    fn empty(&self, level: u32) -> Arc<ParallelNode> {
+        let mut node = self.leaf(false);
+        for _ in 0..level {
+            node = self.node([node.clone(), node.clone(), node.clone(), node]);
+        }
+        node
+    }
+
+    fn node_cell(node: &ParallelNode, x: u64, y: u64) -> bool {
+        match &node.children {
+            None => node.population > 0,
+            Some(children) => {
+                let half = 1 << (node.level - 1);
+                let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+                Self::node_cell(&children[quadrant], x % half, y % half)
+            }
+        }
+    }
+
+    fn with_cell(&self, node: &Arc<ParallelNode>, x: u64, y: u64, alive: bool) -> Arc<ParallelNode> {
+        if node.level == 0 {
+            return self.leaf(alive);
+        }
+        let half = 1 << (node.level - 1);
+        let mut children = node.quadrants().clone();
+        let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+        children[quadrant] = self.with_cell(&children[quadrant], x % half, y % half, alive);
+        self.node(children)
+    }
+
+    fn compute_level2(&self, node: &ParallelNode) -> Arc<ParallelNode> {
+        let mut block = 0u16;
+        for y in 0..4 {
+            for x in 0..4 {
+                if Self::node_cell(node, x, y) {
+                    block |= 1 << (y * 4 + x);
+                }
+            }
+        }
+        self.node(self.rule.level2(block).map(|alive| self.leaf(alive)))
+    }
+
+    fn centered_subnode(&self, node: &ParallelNode) -> Arc<ParallelNode> {
+        let [nw, ne, sw, se] = node.quadrants();
+        self.node([
+            nw.quadrants()[3].clone(),
+            ne.quadrants()[2].clone(),
+            sw.quadrants()[1].clone(),
+            se.quadrants()[0].clone(),
+        ])
+    }
+
+    fn centered_horizontal(&self, w: &ParallelNode, e: &ParallelNode) -> Arc<ParallelNode> {
+        let (w, e) = (w.quadrants(), e.quadrants());
+        self.node([w[1].clone(), e[0].clone(), w[3].clone(), e[2].clone()])
+    }
+
+    fn centered_vertical(&self, n: &ParallelNode, s: &ParallelNode) -> Arc<ParallelNode> {
+        let (n, s) = (n.quadrants(), s.quadrants());
+        self.node([n[2].clone(), n[3].clone(), s[0].clone(), s[1].clone()])
+    }
+
+    /// 跳躍 2^j 步後的中心子節點，見 [`crate::Universe`] 的同名方法
+    fn advance(&self, node: &Arc<ParallelNode>, j: u32) -> Arc<ParallelNode> {
+        if let Some(cached) = self.results.get(&(node.id, j)) {
+            return cached.clone();
+        }
+        let res = if node.level == 2 {
+            self.compute_level2(node)
+        } else {
+            let full = j + 2 == node.level;
+            let [nw, ne, sw, se] = node.quadrants();
+            let subs = [
+                nw.clone(),
+                self.centered_horizontal(nw, ne),
+                ne.clone(),
+                self.centered_vertical(nw, sw),
+                self.centered_subnode(node),
+                self.centered_vertical(ne, se),
+                sw.clone(),
+                self.centered_horizontal(sw, se),
+                se.clone(),
+            ];
+            let phase1 = |sub: &Arc<ParallelNode>| {
+                if full {
+                    self.advance(sub, j - 1)
+                } else {
+                    self.centered_subnode(sub)
+                }
+            };
+            let parallel = node.level >= PARALLEL_LEVEL;
+            let n: Vec<Arc<ParallelNode>> = if parallel {
+                subs.par_iter().map(phase1).collect()
+            } else {
+                subs.iter().map(phase1).collect()
+            };
+
+            let j2 = if full { j - 1 } else { j };
+            let quads = [
+                self.node([n[0].clone(), n[1].clone(), n[3].clone(), n[4].clone()]),
+                self.node([n[1].clone(), n[2].clone(), n[4].clone(), n[5].clone()]),
+                self.node([n[3].clone(), n[4].clone(), n[6].clone(), n[7].clone()]),
+                self.node([n[4].clone(), n[5].clone(), n[7].clone(), n[8].clone()]),
+            ];
+            let r: Vec<Arc<ParallelNode>> = if parallel {
+                quads.par_iter().map(|q| self.advance(q, j2)).collect()
+            } else {
+                quads.iter().map(|q| self.advance(q, j2)).collect()
+            };
+            self.node([r[0].clone(), r[1].clone(), r[2].clone(), r[3].clone()])
+        };
+        self.results.insert((node.id, j), res.clone());
+        res
+    }
+
+    fn root_half(&self) -> i64 {
+        1 << (self.root.level - 1)
+    }
+
+    fn expand(&mut self) -> Result<(), RangeError> {
+        check_level(self.root.level + 1)?;
+        let e = self.empty(self.root.level - 1);
+        let [nw, ne, sw, se] = self.root.quadrants().clone();
+        self.root = self.node([
+            self.node([e.clone(), e.clone(), e.clone(), nw]),
+            self.node([e.clone(), e.clone(), ne, e.clone()]),
+            self.node([e.clone(), sw, e.clone(), e.clone()]),
+            self.node([se, e.clone(), e.clone(), e]),
+        ]);
+        Ok(())
+    }
+
+    fn pad(&mut self, level: u32) -> Result<(), RangeError> {
+        while self.root.level < level.max(Self::MIN_LEVEL)
+            || self.centered_subnode(&self.centered_subnode(&self.root)).population != self.root.population
+        {
+            self.expand()?;
+        }
+        Ok(())
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<(), RangeError> {
+        while x < -self.root_half() || x >= self.root_half() || y < -self.root_half() || y >= self.root_half() {
+            self.expand()?;
+        }
+        let half = self.root_half();
+        self.root = self.with_cell(&self.root, (x + half) as u64, (y + half) as u64, alive);
+        Ok(())
+    }
+
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        let half = self.root_half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+        Self::node_cell(&self.root, (x + half) as u64, (y + half) as u64)
+    }
+
+    /// 以原點為中心的根節點；步進後換成新的節點，舊的根節點仍可繼續使用
+    pub fn root(&self) -> &Arc<ParallelNode> {
+        &self.root
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.root.population
+    }
+
+    /// hash-consing 表中的節點數
+    pub fn node_count(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.cells().into_iter().fold(None, |bounds, (x, y)| {
+            Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            })
+        })
+    }
+
+    pub fn cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::with_capacity(self.population() as usize);
+        let half = self.root_half();
+        let mut stack = vec![(&self.root, -half, -half)];
+        while let Some((node, x, y)) = stack.pop() {
+            if node.population == 0 {
+                continue;
+            }
+            match &node.children {
+                None => cells.push((x, y)),
+                Some([nw, ne, sw, se]) => {
+                    let half = 1 << (node.level - 1);
+                    stack.extend([(nw, x, y), (ne, x + half, y), (sw, x, y + half), (se, x + half, y + half)]);
+                }
+            }
+        }
+        cells
+    }
+
+    /// 前進 `generations` 個世代，拆成 2 的冪次逐一跳躍
+    pub fn step(&mut self, generations: u64) -> Result<(), RangeError> {
+        check_step(self.generation, generations)?;
+        for j in 0..u64::BITS {
+            if generations & (1 << j) != 0 {
+                self.pad(j + 2)?;
+                self.root = self.advance(&self.root, j);
+                self.generation += 1 << j;
+                if self.memory_usage() > self.memory_budget {
+                    self.collect_garbage();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 設定兩張表的記憶體預算（位元組）
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = bytes;
+    }
+
+    /// 兩張表佔用記憶體的估計值（位元組）
+    pub fn memory_usage(&self) -> usize {
+        self.table.len() * Self::NODE_BYTES + self.results.len() * Self::RESULT_BYTES
+    }
+
+    /// 已執行的垃圾回收次數
+    pub fn collections(&self) -> u64 {
+        self.collections
+    }
+
+    /// 標記並清除：保留從根節點可到達的節點，以及兩端都留下的跳躍結果
+    ///
+    /// 節點編號不會重複使用，被移除的節點即使還有結果指向它，也不會和之後新建的節點混淆。
+    pub fn collect_garbage(&mut self) {
+        let mut marked = HashSet::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            if let Some(children) = &node.children {
+                if marked.insert(node.id) {
+                    stack.extend(children);
+                }
+            }
+        }
+        // leaf 不在表中，編號 0 與 1 永遠有效
+        let alive = |id: &u64| *id < 2 || marked.contains(id);
+        self.table.retain(|_, node| alive(&node.id));
+        self.results.retain(|&(id, _), result| alive(&id) && alive(&result.id));
+        self.collections += 1;
+    }
+
+    /// 讀取 macrocell 格式，沿用 [`Universe::read_macrocell`] 的解析再轉成可共用的節點
+    pub fn read_macrocell(reader: impl BufRead) -> Result<ParallelUniverse, MacrocellError> {
+        let universe = Universe::read_macrocell(reader)?;
+        let mut parallel = ParallelUniverse::with_rule(universe.rule.clone());
+        let mut nodes = HashMap::new();
+        parallel.root = parallel.import(&universe.root, &mut nodes);
+        parallel.generation = universe.generation;
+        Ok(parallel)
+    }
+
+    fn import(&self, node: &Node, nodes: &mut HashMap<*const Node, Arc<ParallelNode>>) -> Arc<ParallelNode> {
+        if node.level == 0 {
+            return self.leaf(node.population > 0);
+        }
+        if let Some(imported) = nodes.get(&(node as *const Node)) {
+            return imported.clone();
+        }
+        let imported = self.node([node.nw(), node.ne(), node.sw(), node.se()].map(|child| self.import(child, nodes)));
+        nodes.insert(node as *const Node, imported.clone());
+        imported
+    }
+}
@@ -0,0 +1,98 @@
+//! 生命遊戲圖樣常用的 RLE 格式
+//!
+//! `#` 開頭的註解行之後是 `x = 3, y = 3, rule = B3/S23` 標頭，接著以 `b` 表示死細胞、
+//! `o` 表示活細胞、`$` 換列，前綴數字為重複次數，`!` 結束。座標以圖樣左上角為原點。
+
+use std::fmt;
+
+use crate::{Rule, Universe};
+
+/// 讀取 RLE 時的錯誤
+#[derive(Debug)]
+pub struct RleError(String);
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RleError {}
+
+/// 解析後的圖樣，沒有 `rule` 標頭時為康威生命遊戲
+#[derive(Debug, Clone, PartialEq)]
+pub struct RlePattern {
+    pub cells: Vec<(i64, i64)>,
+    pub rule: Rule,
+}
+
+impl RlePattern {
+    /// 一個 RLE 圖樣最多的活細胞數，避免像 `4000000000o` 這樣的輸入佔滿記憶體；更大的圖樣請用 macrocell
+    pub const MAX_CELLS: usize = 1 << 24;
+
+    pub fn parse(text: &str) -> Result<RlePattern, RleError> {
+        // 座標必須落在根節點 level 上限的範圍內
+        let limit = 1i64 << (Universe::MAX_LEVEL - 1);
+        let advance = |position: i64, run: i64| {
+            position
+                .checked_add(run)
+                .filter(|&position| position <= limit)
+                .ok_or_else(|| RleError("pattern exceeds the coordinate range".to_owned()))
+        };
+        let mut rule = Rule::default();
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')).peekable();
+        if let Some(header) = lines.next_if(|line| line.starts_with('x')) {
+            for field in header.split(',') {
+                if let Some(("rule", value)) = field.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+                    rule = Rule::parse(value).map_err(|e| RleError(e.to_string()))?;
+                }
+            }
+        }
+
+        let mut cells = Vec::new();
+        let (mut x, mut y) = (0i64, 0i64);
+        let mut count: Option<i64> = None;
+        'body: for line in lines {
+            for c in line.chars() {
+                if let Some(digit) = c.to_digit(10) {
+                    let run = count.unwrap_or(0).checked_mul(10).and_then(|run| run.checked_add(digit as i64));
+                    count = Some(run.ok_or_else(|| RleError(format!("run length too large in {}", line)))?);
+                    continue;
+                }
+                let run = count.take().unwrap_or(1);
+                match c {
+                    'b' | '.' => x = advance(x, run)?,
+                    '$' => {
+                        y = advance(y, run)?;
+                        x = 0;
+                    }
+                    '!' => break 'body,
+                    // 多狀態規則的其他字母一律視為活細胞
+                    c if c.is_ascii_alphabetic() => {
+                        let end = advance(x, run)?;
+                        if y >= limit || cells.len() as i64 + run > Self::MAX_CELLS as i64 {
+                            return Err(RleError(format!("pattern has more than {} live cells", Self::MAX_CELLS)));
+                        }
+                        cells.extend((x..end).map(|x| (x, y)));
+                        x = end;
+                    }
+                    c if c.is_whitespace() => {}
+                    c => return Err(RleError(format!("unexpected character '{}'", c))),
+                }
+            }
+        }
+        Ok(RlePattern { cells, rule })
+    }
+}
+
+impl Universe {
+    /// 讀取 RLE 圖樣，包含標頭中的規則
+    pub fn read_rle(text: &str) -> Result<Universe, RleError> {
+        let pattern = RlePattern::parse(text)?;
+        let mut universe = Universe::with_rule(pattern.rule);
+        for (x, y) in pattern.cells {
+            universe.set_cell(x, y, true).map_err(|e| RleError(e.to_string()))?;
+        }
+        Ok(universe)
+    }
+}
@@ -0,0 +1,209 @@
+//! 生命遊戲類規則：outer-totalistic（如 `B36/S23`）與 isotropic non-totalistic（Hensel 記號，如 `B2-a/S12`）
+//!
+//! 規則先展開成 3x3 鄰域的下一代狀態表，再預先算出所有 4x4 區塊前進一代後的中心 2x2，
+//! HashLife 的 level 2 基底因此只是一次查表。
+
+use std::fmt;
+
+/// 解析規則字串時的錯誤
+#[derive(Debug)]
+pub struct RuleError(String);
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// 每個鄰居數可用的 Hensel 字母，5 到 8 與 3 到 0 相同
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrtwyz"];
+
+/// 與 `LETTERS` 對應的代表鄰域，位元順序同 Golly：3x3 列優先、中心為第 4 位元
+const REPRESENTATIVES: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// 中心細胞在 3x3 鄰域索引中的位元
+const CENTER: usize = 1 << 4;
+
+#[derive(Clone)]
+pub struct Rule {
+    name: String,
+    /// 以 3x3 鄰域（第 y*3+x 位元）為索引的中心細胞下一代狀態
+    cells: Box<[bool; 512]>,
+    /// 以 4x4 區塊（第 y*4+x 位元）為索引，中心 2x2 下一代依 nw、ne、sw、se 存在低 4 位元
+    level2: Box<[u8]>,
+}
+
+impl Default for Rule {
+    /// 康威生命遊戲 B3/S23
+    fn default() -> Self {
+        Rule::parse("B3/S23").unwrap()
+    }
+}
+
+/// 兩個規則的每個鄰域都有相同結果即視為相等，不論寫法
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+impl Eq for Rule {}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rule({})", self.name)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Rule {
+    /// 解析 `B3/S23`、`b3s23` 或舊式的 `23/3`（存活/誕生）；
+    /// 數字後可接 Hensel 字母只取部分鄰域，或以 `-` 開頭排除部分鄰域
+    pub fn parse(text: &str) -> Result<Rule, RuleError> {
+        let text = text.trim();
+        let lower = text.to_ascii_lowercase();
+        let (birth, survival) = if let Some(rest) = lower.strip_prefix('b') {
+            let Some(split) = rest.find('s') else {
+                return Err(RuleError(format!("missing survival conditions in {}", text)));
+            };
+            (rest[..split].trim_end_matches('/'), &rest[split + 1..])
+        } else if let Some(rest) = lower.strip_prefix('s') {
+            let Some(split) = rest.find('b') else {
+                return Err(RuleError(format!("missing birth conditions in {}", text)));
+            };
+            (&rest[split + 1..], rest[..split].trim_end_matches('/'))
+        } else if let Some((survival, birth)) = lower.split_once('/') {
+            (birth, survival)
+        } else {
+            return Err(RuleError(format!("unrecognised rule {}", text)));
+        };
+
+        let classes = hensel_classes();
+        let birth_set = parse_conditions(birth, &classes)?;
+        // B0 會讓無限大的空白背景在奇數世代全部變成活細胞，HashLife 的全空節點無法表示
+        if birth_set[0] {
+            return Err(RuleError(format!("B0 rules are not supported: {}", text)));
+        }
+        let survival_set = parse_conditions(survival, &classes)?;
+        let mut cells = Box::new([false; 512]);
+        for (index, next) in cells.iter_mut().enumerate() {
+            let neighbours = neighbour_mask(index);
+            *next = if index & CENTER != 0 { survival_set[neighbours] } else { birth_set[neighbours] };
+        }
+        Ok(Rule::from_cells(format!("B{}/S{}", birth, survival), cells))
+    }
+
+    fn from_cells(name: String, cells: Box<[bool; 512]>) -> Rule {
+        let mut level2 = vec![0u8; 1 << 16].into_boxed_slice();
+        for (block, result) in level2.iter_mut().enumerate() {
+            for (i, (cx, cy)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+                let mut index = 0;
+                for dy in 0..3 {
+                    for dx in 0..3 {
+                        if block & (1 << ((cy + dy - 1) * 4 + cx + dx - 1)) != 0 {
+                            index |= 1 << (dy * 3 + dx);
+                        }
+                    }
+                }
+                if cells[index] {
+                    *result |= 1 << i;
+                }
+            }
+        }
+        Rule { name, cells, level2 }
+    }
+
+    /// 4x4 區塊前進一代後的中心 2x2
+    pub(crate) fn level2(&self, block: u16) -> [bool; 4] {
+        let bits = self.level2[block as usize];
+        [bits & 1 != 0, bits & 2 != 0, bits & 4 != 0, bits & 8 != 0]
+    }
+}
+
+/// 去掉中心位元，得到 8 位元的鄰居遮罩
+fn neighbour_mask(index: usize) -> usize {
+    (index & 0b1111) | ((index >> 5) << 4)
+}
+
+/// 每個鄰居遮罩所屬的 Hensel 字母；超過 4 個鄰居時與其補集同字母
+fn hensel_classes() -> [u8; 256] {
+    // 8 種旋轉與鏡射
+    type Transform = fn(i32, i32) -> (i32, i32);
+    let transforms: [Transform; 8] = [
+        |x, y| (x, y),
+        |x, y| (-y, x),
+        |x, y| (-x, -y),
+        |x, y| (y, -x),
+        |x, y| (-x, y),
+        |x, y| (x, -y),
+        |x, y| (y, x),
+        |x, y| (-y, -x),
+    ];
+    let mut classes = [0u8; 256];
+    for (count, representatives) in REPRESENTATIVES.iter().enumerate() {
+        let letters = LETTERS[count].as_bytes();
+        for (i, &representative) in representatives.iter().enumerate() {
+            let letter = letters.get(i).copied().unwrap_or(0);
+            for transform in transforms {
+                let mut mask = 0;
+                for bit in (0..9).filter(|bit| representative & (1 << bit) != 0) {
+                    let (x, y) = transform(bit % 3 - 1, bit / 3 - 1);
+                    mask |= 1 << ((y + 1) * 3 + x + 1);
+                }
+                let mask = neighbour_mask(mask);
+                classes[mask] = letter;
+                // 4 個鄰居的補集仍是 4 個鄰居，各字母已各自列出
+                if count < 4 {
+                    classes[!mask & 0xff] = letter;
+                }
+            }
+        }
+    }
+    classes
+}
+
+/// 把 `36`、`2-a`、`3ce4` 之類的條件展開成 256 個鄰居遮罩是否符合
+fn parse_conditions(text: &str, classes: &[u8; 256]) -> Result<[bool; 256], RuleError> {
+    let mut set = [false; 256];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let Some(count) = c.to_digit(10).filter(|&d| d <= 8) else {
+            return Err(RuleError(format!("unexpected '{}' in {}", c, text)));
+        };
+        let exclude = chars.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = chars.next_if(char::is_ascii_lowercase) {
+            letters.push(letter);
+        }
+        let valid = LETTERS[4 - (count as i32 - 4).unsigned_abs() as usize];
+        if let Some(letter) = letters.chars().find(|&letter| !valid.contains(letter)) {
+            return Err(RuleError(format!("'{}' is not a valid letter for {} neighbours", letter, count)));
+        }
+        if exclude && letters.is_empty() {
+            return Err(RuleError(format!("missing letters after {}- in {}", count, text)));
+        }
+        for (mask, matched) in set.iter_mut().enumerate() {
+            if mask.count_ones() != count {
+                continue;
+            }
+            let listed = letters.contains(classes[mask] as char);
+            if letters.is_empty() || listed != exclude {
+                *matched = true;
+            }
+        }
+    }
+    Ok(set)
+}
@@ -0,0 +1,134 @@
+//! 以隨機圖樣比較 HashLife 與逐格模擬的結果
+
+use hashlife_br::{ArenaUniverse, ParallelUniverse, Rule, Universe};
+
+/// outer-totalistic 規則：以鄰居數為位元的誕生與存活條件
+#[derive(Clone, Copy)]
+struct Totalistic {
+    name: &'static str,
+    birth: u16,
+    survival: u16,
+}
+
+const LIFE: Totalistic = Totalistic { name: "B3/S23", birth: 1 << 3, survival: 1 << 2 | 1 << 3 };
+const HIGHLIFE: Totalistic = Totalistic { name: "B36/S23", birth: 1 << 3 | 1 << 6, survival: 1 << 2 | 1 << 3 };
+
+/// 固定大小、邊界外視為死亡的逐格模擬器
+struct Dense {
+    size: usize,
+    cells: Vec<bool>,
+    rule: Totalistic,
+}
+
+impl Dense {
+    fn new(size: usize, rule: Totalistic) -> Self {
+        Dense { size, cells: vec![false; size * size], rule }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.size + x]
+    }
+
+    fn tick(&mut self) {
+        let mut next = vec![false; self.size * self.size];
+        for y in 1..self.size - 1 {
+            for x in 1..self.size - 1 {
+                let mut count = 0;
+                for dy in 0..3 {
+                    for dx in 0..3 {
+                        if (dx, dy) != (1, 1) && self.get(x + dx - 1, y + dy - 1) {
+                            count += 1;
+                        }
+                    }
+                }
+                let conditions = if self.get(x, y) { self.rule.survival } else { self.rule.birth };
+                next[y * self.size + x] = conditions & (1 << count) != 0;
+            }
+        }
+        self.cells = next;
+    }
+}
+
+/// 簡單的線性同餘產生器，避免額外依賴
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+}
+
+fn compare(rule: Totalistic, seed: u64, soup: usize, generations: u64) {
+    // 光速為每代一格，邊界留足夠空間讓逐格模擬不受截斷影響
+    let margin = generations as usize + 2;
+    let size = soup + 2 * margin;
+    let mut rng = Lcg(seed);
+    let mut dense = Dense::new(size, rule);
+    let mut cells = Vec::new();
+    for y in 0..soup {
+        for x in 0..soup {
+            if rng.next() % 100 < 40 {
+                dense.cells[(y + margin) * size + x + margin] = true;
+                cells.push((x as i64, y as i64));
+            }
+        }
+    }
+
+    let parsed = Rule::parse(rule.name).unwrap();
+    let mut uni = Universe::with_rule(parsed.clone());
+    let mut arena = ArenaUniverse::with_rule(parsed.clone());
+    let mut parallel = ParallelUniverse::with_rule(parsed);
+    for &(x, y) in &cells {
+        uni.set_cell(x, y, true).unwrap();
+        arena.set_cell(x, y, true).unwrap();
+        parallel.set_cell(x, y, true).unwrap();
+    }
+    uni.step(generations).unwrap();
+    arena.step(generations).unwrap();
+    parallel.step(generations).unwrap();
+    for _ in 0..generations {
+        dense.tick();
+    }
+
+    let mut expected = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            if dense.get(x, y) {
+                expected.push((x as i64 - margin as i64, y as i64 - margin as i64));
+            }
+        }
+    }
+    let mut actual = uni.cells();
+    actual.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(actual, expected, "{}, seed {} after {} generations", rule.name, seed, generations);
+
+    let mut actual = arena.cells();
+    actual.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(actual, expected, "arena, {}, seed {} after {} generations", rule.name, seed, generations);
+
+    let mut actual = parallel.cells();
+    actual.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(actual, expected, "parallel, {}, seed {} after {} generations", rule.name, seed, generations);
+}
+
+#[test]
+fn random_soups_match_dense_simulation() {
+    for seed in 0..12 {
+        compare(LIFE, seed, 16, 64);
+    }
+}
+
+#[test]
+fn uneven_step_counts_match_dense_simulation() {
+    for (seed, generations) in [(100, 1), (101, 7), (102, 37), (103, 100)] {
+        compare(LIFE, seed, 12, generations);
+    }
+}
+
+#[test]
+fn highlife_matches_dense_simulation() {
+    for seed in 200..206 {
+        compare(HIGHLIFE, seed, 16, 48);
+    }
+}
@@ -0,0 +1,129 @@
+use hashlife_br::{ArenaUniverse, ParallelUniverse, Rule, Universe};
+
+const GLIDER_MC: &str = "[M2] (golly 2.0)
+#R B3/S23
+#G 42
+.*$..*$***$
+4 0 0 0 1
+";
+
+#[test]
+fn reads_golly_macrocell() {
+    let uni = Universe::read_macrocell(GLIDER_MC.as_bytes()).unwrap();
+    assert_eq!(uni.generation(), 42);
+    assert_eq!(uni.population(), 5);
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        assert!(uni.get_cell(x, y));
+    }
+}
+
+#[test]
+fn root_can_be_walked_without_expanding_cells() {
+    let uni = Universe::read_macrocell(GLIDER_MC.as_bytes()).unwrap();
+    let root = uni.root();
+    assert_eq!((root.level(), root.population()), (4, 5));
+    let [nw, ne, sw, se] = root.children().unwrap();
+    assert_eq!([nw.population(), ne.population(), sw.population(), se.population()], [0, 0, 0, 5]);
+    // 空的子節點共用同一個節點
+    assert_eq!(nw.id(), ne.id());
+    let leaf = se.children().unwrap()[0].children().unwrap()[0].children().unwrap()[0];
+    assert_eq!(leaf.level(), 0);
+    assert!(leaf.children().is_none());
+}
+
+#[test]
+fn round_trips_stepped_universe() {
+    let mut uni = Universe::from_cells(&[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]).unwrap();
+    uni.step(500).unwrap();
+
+    let mut file = Vec::new();
+    uni.write_macrocell(&mut file).unwrap();
+    let loaded = Universe::read_macrocell(file.as_slice()).unwrap();
+
+    assert_eq!(loaded.generation(), 500);
+    let (mut a, mut b) = (uni.cells(), loaded.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn arena_shares_the_macrocell_format() {
+    let mut arena = ArenaUniverse::read_macrocell(GLIDER_MC.as_bytes()).unwrap();
+    assert_eq!(arena.generation(), 42);
+    assert_eq!(arena.population(), 5);
+    arena.step(100).unwrap();
+
+    let mut file = Vec::new();
+    arena.write_macrocell(&mut file).unwrap();
+    let loaded = Universe::read_macrocell(file.as_slice()).unwrap();
+    let mut expected = Universe::read_macrocell(GLIDER_MC.as_bytes()).unwrap();
+    expected.step(100).unwrap();
+    assert_eq!(loaded.generation(), 142);
+    let (mut a, mut b) = (loaded.cells(), expected.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn parallel_reads_macrocell_with_its_rule() {
+    // HighLife 中滑翔機的演化與生命遊戲相同
+    let mut parallel = ParallelUniverse::read_macrocell(GLIDER_MC.replace("B3/S23", "B36/S23").as_bytes()).unwrap();
+    assert_eq!(parallel.rule(), &Rule::parse("B36/S23").unwrap());
+    assert_eq!((parallel.generation(), parallel.population()), (42, 5));
+    parallel.step(100).unwrap();
+
+    let mut expected = Universe::read_macrocell(GLIDER_MC.as_bytes()).unwrap();
+    expected.step(100).unwrap();
+    let (mut a, mut b) = (parallel.cells(), expected.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+
+    // 根節點可以在其他執行緒走訪
+    let root = parallel.root().clone();
+    let population = std::thread::spawn(move || root.children().map(|children| children.iter().map(|c| c.population()).sum::<u64>()));
+    assert_eq!(population.join().unwrap(), Some(5));
+}
+
+#[test]
+fn rejects_unknown_rule_and_dangling_references() {
+    assert!(Universe::read_macrocell("[M2]\n#R B9/S23\n".as_bytes()).is_err());
+    assert!(Universe::read_macrocell("[M2]\n4 0 0 0 7\n".as_bytes()).is_err());
+}
+
+#[test]
+fn rejects_levels_beyond_the_coordinate_range() {
+    let Err(error) = Universe::read_macrocell("[M2]\n*$\n63 0 0 0 0\n".as_bytes()) else {
+        panic!("level 63 should be rejected");
+    };
+    assert!(error.to_string().contains("line 3"), "{}", error);
+    assert!(Universe::read_macrocell("[M2]\n99999999999 0 0 0 0\n".as_bytes()).is_err());
+
+    // 最大 level 的空節點仍可讀入
+    let uni = Universe::read_macrocell("[M2]\n62 0 0 0 0\n".as_bytes()).unwrap();
+    assert_eq!(uni.population(), 0);
+}
+
+#[test]
+fn round_trips_rule() {
+    let mut uni = Universe::with_rule(Rule::parse("B36/S23").unwrap());
+    for (x, y) in [(2, 0), (3, 0), (4, 0), (1, 1), (4, 1), (0, 2), (4, 2), (0, 3), (3, 3), (0, 4), (1, 4), (2, 4)] {
+        uni.set_cell(x, y, true).unwrap();
+    }
+    uni.step(12).unwrap();
+
+    let mut file = Vec::new();
+    uni.write_macrocell(&mut file).unwrap();
+    assert!(String::from_utf8_lossy(&file).contains("#R B36/S23"));
+    let mut loaded = Universe::read_macrocell(file.as_slice()).unwrap();
+    assert_eq!(loaded.rule(), uni.rule());
+
+    uni.step(20).unwrap();
+    loaded.step(20).unwrap();
+    let (mut a, mut b) = (uni.cells(), loaded.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
@@ -0,0 +1,182 @@
+use hashlife_br::{ArenaUniverse, ParallelUniverse, RangeError, Universe};
+
+const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+#[test]
+fn cells_round_trip() {
+    let cells = [(-5, 3), (0, 0), (12, -40), (1000, 7)];
+    let uni = Universe::from_cells(&cells).unwrap();
+    for &(x, y) in &cells {
+        assert!(uni.get_cell(x, y));
+    }
+    assert!(!uni.get_cell(1, 1));
+    assert_eq!(uni.population(), 4);
+    assert_eq!(uni.bounding_box(), Some((-5, -40, 1000, 7)));
+
+    let mut listed = uni.cells();
+    listed.sort();
+    let mut expected = cells.to_vec();
+    expected.sort();
+    assert_eq!(listed, expected);
+}
+
+#[test]
+fn empty_universe_has_no_bounding_box() {
+    let mut uni = Universe::new();
+    assert_eq!(uni.bounding_box(), None);
+    uni.set_cell(3, 3, true).unwrap();
+    uni.set_cell(3, 3, false).unwrap();
+    assert_eq!(uni.population(), 0);
+    assert_eq!(uni.bounding_box(), None);
+}
+
+#[test]
+fn blinker_oscillates() {
+    let mut uni = Universe::from_cells(&[(-1, 0), (0, 0), (1, 0)]).unwrap();
+    uni.step(1).unwrap();
+    assert_eq!(uni.generation(), 1);
+    assert_eq!(uni.bounding_box(), Some((0, -1, 0, 1)));
+    uni.step(1).unwrap();
+    assert_eq!(uni.bounding_box(), Some((-1, 0, 1, 0)));
+}
+
+#[test]
+fn r_pentomino_stabilizes_at_1103() {
+    let mut uni = Universe::from_cells(&R_PENTOMINO).unwrap();
+    uni.step(1103).unwrap();
+    assert_eq!(uni.generation(), 1103);
+    assert_eq!(uni.population(), 116);
+}
+
+#[test]
+fn arbitrary_step_matches_single_steps() {
+    let mut jumped = Universe::from_cells(&R_PENTOMINO).unwrap();
+    jumped.step(1000).unwrap();
+
+    let mut stepped = Universe::from_cells(&R_PENTOMINO).unwrap();
+    for _ in 0..1000 {
+        stepped.step(1).unwrap();
+    }
+
+    assert_eq!(jumped.generation(), stepped.generation());
+    let (mut a, mut b) = (jumped.cells(), stepped.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn garbage_collection_keeps_results_correct() {
+    let mut unbounded = Universe::from_cells(&R_PENTOMINO).unwrap();
+    unbounded.step(1103).unwrap();
+
+    let mut bounded = Universe::from_cells(&R_PENTOMINO).unwrap();
+    bounded.set_memory_budget(0);
+    bounded.step(1103).unwrap();
+
+    assert_eq!(bounded.population(), 116);
+    let stats = bounded.stats();
+    assert!(stats.collections > 0);
+    assert!(stats.nodes < unbounded.stats().nodes);
+    assert!(stats.hit_rate() > 0.0);
+}
+
+#[test]
+fn arena_compaction_keeps_results_correct() {
+    let mut unbounded = ArenaUniverse::from_cells(&R_PENTOMINO).unwrap();
+    unbounded.step(1103).unwrap();
+
+    let mut bounded = ArenaUniverse::from_cells(&R_PENTOMINO).unwrap();
+    bounded.set_memory_budget(0);
+    bounded.step(1103).unwrap();
+
+    assert_eq!(bounded.population(), 116);
+    let stats = bounded.stats();
+    assert!(stats.collections > 0);
+    assert!(stats.nodes < unbounded.stats().nodes);
+    assert!(stats.hit_rate() > 0.0);
+
+    // 壓縮後的 arena 仍能繼續演化，結果與未回收的相同
+    bounded.step(500).unwrap();
+    unbounded.step(500).unwrap();
+    let (mut a, mut b) = (bounded.cells(), unbounded.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn parallel_collection_keeps_results_correct() {
+    let mut unbounded = ParallelUniverse::from_cells(&R_PENTOMINO).unwrap();
+    unbounded.step(1103).unwrap();
+
+    let mut bounded = ParallelUniverse::from_cells(&R_PENTOMINO).unwrap();
+    bounded.set_memory_budget(0);
+    bounded.step(1103).unwrap();
+
+    assert_eq!(bounded.population(), 116);
+    assert!(bounded.collections() > 0);
+    assert!(bounded.node_count() < unbounded.node_count());
+    assert!(bounded.memory_usage() < unbounded.memory_usage());
+
+    bounded.step(500).unwrap();
+    unbounded.step(500).unwrap();
+    let (mut a, mut b) = (bounded.cells(), unbounded.cells());
+    a.sort();
+    b.sort();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn parallel_evaluation_is_deterministic() {
+    // 夠大的根節點才會真正分派到多個執行緒
+    let soup: Vec<(i64, i64)> = (0..64i64)
+        .flat_map(|y| (0..64i64).map(move |x| (x, y)))
+        .filter(|&(x, y)| (x * 7 + y * 13 + x * y) % 5 < 2)
+        .collect();
+
+    let mut serial = Universe::from_cells(&soup).unwrap();
+    serial.step(1000).unwrap();
+    let mut expected = serial.cells();
+    expected.sort();
+
+    for _ in 0..3 {
+        let mut parallel = ParallelUniverse::from_cells(&soup).unwrap();
+        parallel.step(1000).unwrap();
+        let mut actual = parallel.cells();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+}
+
+/// 三種宇宙在座標與跳躍的上限都回傳錯誤，不會溢位
+macro_rules! check_limits {
+    ($universe:ty) => {{
+        let edge = (1i64 << 61) - 1;
+        let mut uni = <$universe>::new();
+        assert_eq!(uni.set_cell(i64::MAX, 0, true), Err(RangeError));
+        assert_eq!(uni.set_cell(0, i64::MIN, true), Err(RangeError));
+        assert_eq!(uni.population(), 0);
+
+        uni.set_cell(edge, -edge - 1, true).unwrap();
+        assert!(uni.get_cell(edge, -edge - 1));
+        // 邊緣的細胞沒有空間再補一圈空白
+        assert_eq!(uni.step(1), Err(RangeError));
+        assert_eq!(uni.generation(), 0);
+
+        let mut blinker = <$universe>::from_cells(&[(-1, 0), (0, 0), (1, 0)]).unwrap();
+        assert_eq!(blinker.step(1 << 61), Err(RangeError));
+        assert_eq!(blinker.step(u64::MAX), Err(RangeError));
+        assert_eq!(blinker.generation(), 0);
+        blinker.step((1 << 60) + 1).unwrap();
+        assert_eq!(blinker.generation(), (1 << 60) + 1);
+        assert!(blinker.get_cell(0, -1) && blinker.get_cell(0, 1));
+    }};
+}
+
+#[test]
+fn coordinate_and_jump_limits() {
+    check_limits!(Universe);
+    check_limits!(ArenaUniverse);
+    check_limits!(ParallelUniverse);
+}
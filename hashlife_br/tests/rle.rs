@@ -0,0 +1,30 @@
+use hashlife_br::{Rule, RlePattern, Universe};
+
+#[test]
+fn parses_header_rule_and_runs() {
+    let pattern = RlePattern::parse("#N Glider\nx = 3, y = 3, rule = B36/S23\nbo$2bo$\n3o!").unwrap();
+    assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    assert_eq!(pattern.rule, Rule::parse("B36/S23").unwrap());
+
+    let uni = Universe::read_rle("2o2$2o!").unwrap();
+    assert!(uni.get_cell(1, 2));
+    assert!(!uni.get_cell(0, 1));
+    assert_eq!(uni.rule(), &Rule::default());
+}
+
+#[test]
+fn rejects_bad_input() {
+    assert!(RlePattern::parse("x = 1, y = 1, rule = B9/S\no!").is_err());
+    assert!(RlePattern::parse("3o%!").is_err());
+    assert!(RlePattern::parse("99999999999999999999o!").is_err());
+}
+
+#[test]
+fn rejects_huge_runs_without_allocating() {
+    assert!(RlePattern::parse("4000000000o!").is_err());
+    assert!(RlePattern::parse("9223372036854775807b9223372036854775807bo!").is_err());
+    assert!(RlePattern::parse("3000000000000000000$o!").is_err());
+    // 很長的空白不佔記憶體，只要還在座標範圍內就可以
+    let pattern = RlePattern::parse("4000000000bo$4000000000$o!").unwrap();
+    assert_eq!(pattern.cells, vec![(4000000000, 0), (0, 4000000001)]);
+}
@@ -0,0 +1,168 @@
+use hashlife_br::{Rule, Universe};
+
+#[test]
+fn parses_common_notations() {
+    let life = Rule::default();
+    assert_eq!(Rule::parse("b3s23").unwrap(), life);
+    assert_eq!(Rule::parse("23/3").unwrap(), life);
+    assert_eq!(Rule::parse("S23/B3").unwrap(), life);
+    assert_ne!(Rule::parse("B36/S23").unwrap(), life);
+    assert_eq!(Rule::parse("b36/s23").unwrap().to_string(), "B36/S23");
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    for text in ["B9/S23", "B2z/S23", "B3", "B2-/S", "B3/S2x", "B0/S8", "B03/S23", "s23/b0123"] {
+        assert!(Rule::parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn rejects_b0_with_reason() {
+    let error = Rule::parse("B03/S23").unwrap_err();
+    assert!(error.to_string().contains("B0"), "{}", error);
+    assert!(Rule::parse("B1/S0").is_ok());
+}
+
+#[test]
+fn listing_every_hensel_letter_matches_totalistic() {
+    let isotropic = Rule::parse("B1ce2ceaikn3ceaiknjqry4ceaiknjqrtwyz/S5ceaiknjqry6ceaikn7ce8").unwrap();
+    assert_eq!(isotropic, Rule::parse("B1234/S5678").unwrap());
+    assert_eq!(Rule::parse("B2-a/S").unwrap(), Rule::parse("B2ceikn/S").unwrap());
+    assert_ne!(Rule::parse("B2a/S").unwrap(), Rule::parse("B2c/S").unwrap());
+}
+
+/// 把 (x, y) 順時針旋轉 90 度
+fn rotate(cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut rotated: Vec<_> = cells.iter().map(|&(x, y)| (-y, x)).collect();
+    rotated.sort();
+    rotated
+}
+
+#[test]
+fn isotropic_rules_commute_with_rotation() {
+    let rule = Rule::parse("B2e3ai/S1e23-a4kz").unwrap();
+    let soup: Vec<(i64, i64)> = (0..64)
+        .filter(|i| (i * 37 + i / 5) % 3 == 0)
+        .map(|i| (i % 8, i / 8))
+        .collect();
+
+    let mut uni = Universe::with_rule(rule.clone());
+    let mut rotated = Universe::with_rule(rule);
+    for (&(x, y), &(rx, ry)) in soup.iter().zip(&rotate(&soup)) {
+        uni.set_cell(x, y, true).unwrap();
+        rotated.set_cell(rx, ry, true).unwrap();
+    }
+    uni.step(40).unwrap();
+    rotated.step(40).unwrap();
+
+    let mut actual = rotated.cells();
+    actual.sort();
+    assert!(!actual.is_empty());
+    assert_eq!(actual, rotate(&uni.cells()));
+}
+
+/// 以規則建立宇宙並放入細胞
+fn universe(rule: &str, cells: &[(i64, i64)]) -> Universe {
+    populated(Rule::parse(rule).unwrap(), cells)
+}
+
+fn populated(rule: Rule, cells: &[(i64, i64)]) -> Universe {
+    let mut uni = Universe::with_rule(rule);
+    for &(x, y) in cells {
+        uni.set_cell(x, y, true).unwrap();
+    }
+    uni
+}
+
+/// 把 3x3 圖（`O` 為活細胞）轉成以中心為原點的座標，中心必須空著
+fn neighbourhood(picture: &str) -> Vec<(i64, i64)> {
+    let rows: Vec<&str> = picture.split('/').collect();
+    assert_eq!(rows[1].as_bytes()[1], b'.');
+    (0..3)
+        .flat_map(|y| (0..3).map(move |x| (x, y)))
+        .filter(|&(x, y)| rows[y as usize].as_bytes()[x as usize] == b'O')
+        .map(|(x, y)| (x - 1, y - 1))
+        .collect()
+}
+
+/// Golly 的 Hensel 記號中 c、e、k、a 字母的代表鄰域
+const HENSEL: [(&str, &str); 14] = [
+    ("1c", "O../.../..."),
+    ("1e", ".O./.../..."),
+    ("2c", "O.O/.../..."),
+    ("2e", ".O./O../..."),
+    ("2k", "O../..O/..."),
+    ("2a", "OO./.../..."),
+    ("3c", "O.O/.../O.."),
+    ("3e", ".O./O.O/..."),
+    ("3k", ".O./..O/O.."),
+    ("3a", "OO./O../..."),
+    ("4c", "O.O/.../O.O"),
+    ("4e", ".O./O.O/.O."),
+    ("4k", "OO./..O/O.."),
+    ("4a", "OOO/O../..."),
+];
+
+#[test]
+fn hensel_letters_match_golly_neighbourhoods() {
+    let rules: Vec<(&str, Rule)> = HENSEL
+        .iter()
+        .map(|&(condition, _)| (condition, Rule::parse(&format!("B{}/S", condition)).unwrap()))
+        .collect();
+    for (condition, picture) in HENSEL {
+        let mut cells = neighbourhood(picture);
+        for _ in 0..4 {
+            for (other, rule) in rules.iter().filter(|(other, _)| other.as_bytes()[0] == condition.as_bytes()[0]) {
+                let mut uni = populated(rule.clone(), &cells);
+                uni.step(1).unwrap();
+                assert_eq!(uni.get_cell(0, 0), *other == condition, "{} under B{}/S", picture, other);
+            }
+            // 鏡射後字母不變
+            let rule = rules.iter().find(|(other, _)| *other == condition).unwrap().1.clone();
+            let mut mirrored = populated(rule, &cells.iter().map(|&(x, y)| (-x, y)).collect::<Vec<_>>());
+            mirrored.step(1).unwrap();
+            assert!(mirrored.get_cell(0, 0), "mirrored {}", picture);
+            cells = rotate(&cells);
+        }
+    }
+}
+
+#[test]
+fn just_friends_known_answers() {
+    // B2-a/S12：骨牌兩側的空格都是 2a 鄰域，不會誕生，因此是靜物
+    let mut domino = universe("B2-a/S12", &[(0, 0), (1, 0)]);
+    domino.step(10).unwrap();
+    assert_eq!(domino.cells().len(), 2);
+    assert!(domino.get_cell(0, 0) && domino.get_cell(1, 0));
+    // 同樣的骨牌在 B2/S12 會長大
+    let mut growing = universe("B2/S12", &[(0, 0), (1, 0)]);
+    growing.step(1).unwrap();
+    assert!(growing.population() > 2);
+
+    // 對角的兩格在 2e 的位置誕生成方塊，方塊每格有 3 個鄰居而死光
+    let mut diagonal = universe("B2-a/S12", &[(0, 0), (1, 1)]);
+    diagonal.step(1).unwrap();
+    let mut block = diagonal.cells();
+    block.sort();
+    assert_eq!(block, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+    diagonal.step(1).unwrap();
+    assert_eq!(diagonal.population(), 0);
+}
+
+#[test]
+fn tlife_known_answers() {
+    // tlife 排除 2i 存活：閃爍器的中心兩側鄰居相對，會死去，兩代後全滅
+    let mut blinker = universe("B3/S2-i34q", &[(-1, 0), (0, 0), (1, 0)]);
+    blinker.step(1).unwrap();
+    let mut cells = blinker.cells();
+    cells.sort();
+    assert_eq!(cells, [(0, -1), (0, 1)]);
+    blinker.step(1).unwrap();
+    assert_eq!(blinker.population(), 0);
+
+    // 方塊每格 3 個鄰居，在 tlife 仍是靜物
+    let mut block = universe("B3/S2-i34q", &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+    block.step(100).unwrap();
+    assert_eq!(block.population(), 4);
+}
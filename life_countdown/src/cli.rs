@@ -0,0 +1,247 @@
+//! 不開視窗的命令列模式，與圖形介面讀寫同一份設定檔
+//!
+//! - `life_countdown status [--json]`：剩餘天數、人生進度、年齡與下一個目標
+//! - `life_countdown set --birthday 1990-01-02`：設定目前人資料的生日
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde_json::{json, Value};
+
+use crate::i18n;
+use crate::profile::{Profiles, UserData};
+use crate::storage::{self, Storage};
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Status { json: bool },
+    Set { birthday: NaiveDate },
+    Help,
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter().map(String::as_str);
+    let command = match args.next() {
+        Some("status") => {
+            let mut json = false;
+            for arg in args.by_ref() {
+                match arg {
+                    "--json" => json = true,
+                    other => return Err(tr!("不明的參數: {}", other)),
+                }
+            }
+            Command::Status { json }
+        }
+        Some("set") => {
+            let mut birthday = None;
+            while let Some(arg) = args.next() {
+                let value = if arg == "--birthday" {
+                    args.next().ok_or(tr!("--birthday 需要日期"))?
+                } else if let Some(value) = arg.strip_prefix("--birthday=") {
+                    value
+                } else {
+                    return Err(tr!("不明的參數: {}", arg));
+                };
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| tr!("日期格式錯誤，請使用 YYYY-MM-DD: {}", value))?;
+                birthday = Some(date);
+            }
+            Command::Set { birthday: birthday.ok_or(tr!("--birthday 需要日期"))? }
+        }
+        Some("help" | "--help" | "-h") => Command::Help,
+        Some(other) => return Err(tr!("不明的指令: {}", other)),
+        None => Command::Help,
+    };
+    Ok(command)
+}
+
+fn usage() -> String {
+    tr!("用法:\n  life_countdown                        開啟視窗\n  life_countdown status [--json]        顯示剩餘時間\n  life_countdown set --birthday YYYY-MM-DD  設定生日").to_string()
+}
+
+/// 保留兩位小數，避免 f32 轉成 JSON 時出現多餘的位數
+fn round(value: f32) -> f64 {
+    (value as f64 * 100.0).round() / 100.0
+}
+
+fn status(user: &UserData, now: NaiveDateTime) -> Option<Value> {
+    let birthday = user.birthday?;
+    let today = now.date();
+    let remaining = user.remaining_time(now)?;
+    let next = user.next_goal(today).map(|(goal, date)| {
+        json!({ "title": goal.title, "date": date, "days": (date - today).num_days() })
+    });
+    Some(json!({
+        "name": user.name,
+        "birthday": birthday,
+        "age": round(user.get_age_in_years()?),
+        "life_expectancy": round(user.effective_life_expectancy()),
+        "expected_end": user.expected_death_date()?,
+        "remaining_days": user.calculate_remaining_days()?,
+        "remaining": {
+            "years": remaining.years,
+            "months": remaining.months,
+            "days": remaining.days,
+            "hours": remaining.hours,
+        },
+        "progress": round(user.calculate_life_progress()?),
+        "next_milestone": next,
+    }))
+}
+
+fn status_text(user: &UserData, now: NaiveDateTime) -> Option<String> {
+    let mut text = tr!(
+        "{}：剩餘 {} 天 · 人生進度 {}% · {} 歲",
+        user.display_name(),
+        user.calculate_remaining_days()?,
+        format!("{:.1}", user.calculate_life_progress()?),
+        format!("{:.1}", user.get_age_in_years()?)
+    );
+    let today = now.date();
+    if let Some((goal, date)) = user.next_goal(today) {
+        text += " · ";
+        text += &tr!("{}（{} 天後）", goal.title, (date - today).num_days());
+    }
+    Some(text)
+}
+
+/// 讀取設定檔並套用其中的語言；還沒有設定檔時為預設值
+pub fn load(storage: &mut Storage) -> Result<Profiles, String> {
+    let loaded = storage.load()?;
+    if let Some(warning) = loaded.warning {
+        eprintln!("{}", warning);
+    }
+    let profiles = loaded.profiles.unwrap_or_default();
+    i18n::set_language(profiles.language);
+    Ok(profiles)
+}
+
+/// 執行指令並傳回要印出的內容
+pub fn execute(storage: &mut Storage, mut profiles: Profiles, command: Command, now: NaiveDateTime) -> Result<String, String> {
+    match command {
+        Command::Help => Ok(usage()),
+        Command::Status { json } => {
+            let user = profiles.active();
+            let missing = || tr!("尚未設定生日，請先執行 life_countdown set --birthday YYYY-MM-DD").to_string();
+            if json {
+                let value = status(user, now).ok_or_else(missing)?;
+                serde_json::to_string_pretty(&value).map_err(|e| tr!("無法序列化資料: {}", e))
+            } else {
+                status_text(user, now).ok_or_else(missing)
+            }
+        }
+        Command::Set { birthday } => {
+            if birthday > now.date() {
+                return Err(tr!("生日不能晚於今天: {}", birthday));
+            }
+            let active = profiles.active;
+            profiles.profiles[active].birthday = Some(birthday);
+            storage.save(&profiles)?;
+            Ok(tr!("已將 {} 的生日設為 {}", profiles.active().display_name(), birthday))
+        }
+    }
+}
+
+/// 視窗程式在 Windows 上沒有主控台，借用啟動它的終端機輸出
+#[cfg(windows)]
+fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// 有命令列參數時執行指令並傳回結束代碼；沒有參數時傳回 None，改開視窗
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.is_empty() {
+        return None;
+    }
+    attach_console();
+    let result = storage::config_dir()
+        .ok_or_else(|| tr!("找不到系統設定目錄，設定將無法保存").to_string())
+        .and_then(|dir| {
+            let mut storage = Storage::new(dir.join("config.json"));
+            // 先讀設定檔套用語言，參數錯誤的訊息才會以使用者選擇的語言顯示
+            let profiles = load(&mut storage)?;
+            let command = parse(args).map_err(|e| format!("{}\n\n{}", e, usage()))?;
+            execute(&mut storage, profiles, command, Local::now().naive_local())
+        });
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goals::{Goal, Recurrence};
+    use std::fs;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_commands() {
+        let birthday = NaiveDate::from_ymd_opt(1990, 1, 2).unwrap();
+        assert_eq!(parse(&args("status")), Ok(Command::Status { json: false }));
+        assert_eq!(parse(&args("status --json")), Ok(Command::Status { json: true }));
+        assert_eq!(parse(&args("set --birthday 1990-01-02")), Ok(Command::Set { birthday }));
+        assert_eq!(parse(&args("set --birthday=1990-01-02")), Ok(Command::Set { birthday }));
+        assert_eq!(parse(&args("--help")), Ok(Command::Help));
+        assert!(parse(&args("set")).is_err());
+        assert!(parse(&args("set --birthday")).is_err());
+        assert!(parse(&args("set --birthday 1990-13-01")).is_err());
+        assert!(parse(&args("status --yaml")).is_err());
+        assert!(parse(&args("reset")).is_err());
+    }
+
+    #[test]
+    fn set_then_status_share_the_config() {
+        let dir = std::env::temp_dir().join(format!("life_countdown_cli_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut storage = Storage::new(dir.join("config.json"));
+
+        let status = |storage: &mut Storage| {
+            let profiles = load(storage).unwrap();
+            execute(storage, profiles, Command::Status { json: true }, now())
+        };
+        assert!(status(&mut storage).is_err());
+
+        let birthday = NaiveDate::from_ymd_opt(1990, 1, 2).unwrap();
+        let profiles = load(&mut storage).unwrap();
+        execute(&mut storage, profiles, Command::Set { birthday }, now()).unwrap();
+        let mut profiles = load(&mut storage).unwrap();
+        assert_eq!(profiles.active().birthday, Some(birthday));
+
+        profiles.profiles[0].goals.push(Goal::new("退休".into(), NaiveDate::from_ymd_opt(2055, 1, 2).unwrap(), Recurrence::Once));
+        storage.save(&profiles).unwrap();
+        let value: Value = serde_json::from_str(&status(&mut storage).unwrap()).unwrap();
+        assert_eq!(value["birthday"], "1990-01-02");
+        assert!(value["remaining_days"].as_i64().unwrap() > 0);
+        assert!(value["progress"].as_f64().unwrap() > 0.0);
+        assert_eq!(value["next_milestone"]["title"], "退休");
+        assert_eq!(value["next_milestone"]["date"], "2055-01-02");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_future_birthdays() {
+        let dir = std::env::temp_dir().join(format!("life_countdown_cli_future_{}", std::process::id()));
+        let mut storage = Storage::new(dir.join("config.json"));
+        let birthday = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        assert!(execute(&mut storage, Profiles::default(), Command::Set { birthday }, now()).is_err());
+        assert!(!dir.join("config.json").exists());
+    }
+}
@@ -0,0 +1,126 @@
+//! 以日曆計算的時間長度：逐月加到起點上，而不是把天數除以 365 或 30
+//!
+//! 每次都從原本的起點加上整數個月，所以 2/29 出生的人在閏年會回到 2/29，
+//! 月底出生的人在較短的月份則落在該月最後一天。
+
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    date.checked_add_months(Months::new(months)).unwrap_or(NaiveDate::MAX)
+}
+
+/// `date` 的 `years` 年後；小數部分依那一年實際的天數換算
+pub fn add_years(date: NaiveDate, years: f32) -> NaiveDate {
+    let years = years.max(0.0);
+    let whole = years.trunc() as u32;
+    let start = add_months(date, whole * 12);
+    let next = add_months(date, (whole + 1) * 12);
+    let days = (next - start).num_days() as f64 * years.fract() as f64;
+    start + Duration::days(days.round() as i64)
+}
+
+/// 從 `from` 到 `to` 的整數年數加上今年已經過的比例
+pub fn years_between(from: NaiveDate, to: NaiveDate) -> f32 {
+    if to <= from {
+        return 0.0;
+    }
+    let mut whole = (to.year() - from.year()).max(0) as u32;
+    while whole > 0 && add_months(from, whole * 12) > to {
+        whole -= 1;
+    }
+    let start = add_months(from, whole * 12);
+    let next = add_months(from, (whole + 1) * 12);
+    whole as f32 + (to - start).num_days() as f32 / (next - start).num_days() as f32
+}
+
+/// 拆成年、月、日、時、分、秒的時間長度
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Breakdown {
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+}
+
+impl Breakdown {
+    /// `from` 到 `to` 的時間；`to` 不晚於 `from` 時為零
+    pub fn between(from: NaiveDateTime, to: NaiveDateTime) -> Self {
+        if to <= from {
+            return Breakdown::default();
+        }
+        let add = |months: u32| from.checked_add_months(Months::new(months)).unwrap_or(NaiveDateTime::MAX);
+        let mut months = ((to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32).max(0) as u32;
+        while months > 0 && add(months) > to {
+            months -= 1;
+        }
+        let seconds = (to - add(months)).num_seconds() as u32;
+        Breakdown {
+            years: months / 12,
+            months: months % 12,
+            days: seconds / 86_400,
+            hours: seconds % 86_400 / 3_600,
+            minutes: seconds % 3_600 / 60,
+            seconds: seconds % 60,
+        }
+    }
+
+    /// 時:分:秒
+    pub fn clock(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hours, self.minutes, self.seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn breakdown(from: NaiveDate, to: NaiveDate) -> (u32, u32, u32) {
+        let b = Breakdown::between(from.and_hms_opt(0, 0, 0).unwrap(), to.and_hms_opt(0, 0, 0).unwrap());
+        (b.years, b.months, b.days)
+    }
+
+    #[test]
+    fn leap_day_birthdays() {
+        let birthday = date(2000, 2, 29);
+        assert_eq!(add_years(birthday, 1.0), date(2001, 2, 28));
+        assert_eq!(add_years(birthday, 4.0), date(2004, 2, 29));
+        assert_eq!(add_years(birthday, 80.0), date(2080, 2, 29));
+        assert_eq!(add_years(date(2023, 1, 1), 0.5), date(2023, 7, 3));
+        assert_eq!(add_years(date(2024, 1, 1), 0.5), date(2024, 7, 2));
+
+        assert_eq!(years_between(birthday, date(2001, 2, 27)), 364.0 / 365.0);
+        assert_eq!(years_between(birthday, date(2001, 2, 28)), 1.0);
+        assert_eq!(years_between(birthday, date(2004, 2, 29)), 4.0);
+        assert_eq!(breakdown(birthday, date(2004, 2, 28)), (3, 11, 30));
+        assert_eq!(breakdown(date(2024, 2, 1), date(2024, 3, 1)), (0, 1, 0));
+        assert_eq!(breakdown(date(2023, 2, 1), date(2023, 3, 1)), (0, 1, 0));
+        assert_eq!(breakdown(date(2024, 1, 1), date(2025, 1, 1)), (1, 0, 0));
+    }
+
+    #[test]
+    fn month_end_birthdays() {
+        let birthday = date(1990, 1, 31);
+        assert_eq!(breakdown(birthday, date(1990, 2, 28)), (0, 1, 0));
+        assert_eq!(breakdown(birthday, date(1990, 3, 30)), (0, 1, 30));
+        assert_eq!(breakdown(birthday, date(1990, 3, 31)), (0, 2, 0));
+        assert_eq!(breakdown(date(1990, 8, 31), date(1991, 9, 30)), (1, 1, 0));
+        assert_eq!(breakdown(date(1990, 12, 31), date(1991, 12, 30)), (0, 11, 30));
+        assert_eq!(add_years(date(1990, 12, 31), 80.0), date(2070, 12, 31));
+    }
+
+    #[test]
+    fn hours_minutes_seconds() {
+        let from = date(2024, 12, 31).and_hms_opt(22, 15, 0).unwrap();
+        let to = date(2025, 3, 1).and_hms_opt(1, 0, 30).unwrap();
+        let b = Breakdown::between(from, to);
+        assert_eq!((b.years, b.months, b.days), (0, 2, 0));
+        assert_eq!(b.clock(), "02:45:30");
+        assert_eq!(Breakdown::between(to, from), Breakdown::default());
+    }
+}
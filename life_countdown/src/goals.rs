@@ -0,0 +1,120 @@
+//! 目標與里程碑：有日期、可重複，並記錄完成到哪一次
+
+use chrono::{Duration, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    #[default]
+    Once,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Recurrence {
+    pub const ALL: [Recurrence; 4] = [Recurrence::Once, Recurrence::Weekly, Recurrence::Monthly, Recurrence::Yearly];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Recurrence::Once => tr!("不重複"),
+            Recurrence::Weekly => tr!("每週"),
+            Recurrence::Monthly => tr!("每月"),
+            Recurrence::Yearly => tr!("每年"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub title: String,
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub recurrence: Recurrence,
+    /// 最近一次標記完成的日期；重複的目標會從這之後的下一次繼續
+    #[serde(default)]
+    pub completed: Option<NaiveDate>,
+}
+
+impl Goal {
+    pub fn new(title: String, date: NaiveDate, recurrence: Recurrence) -> Self {
+        Goal { title, date, recurrence, completed: None }
+    }
+
+    /// 第 `n` 次發生的日期，第 0 次即 `date`；月底的日期在短月份會落在該月最後一天
+    fn occurrence(&self, n: u32) -> Option<NaiveDate> {
+        match self.recurrence {
+            Recurrence::Once => (n == 0).then_some(self.date),
+            Recurrence::Weekly => self.date.checked_add_signed(Duration::weeks(n as i64)),
+            Recurrence::Monthly => self.date.checked_add_months(Months::new(n)),
+            Recurrence::Yearly => self.date.checked_add_months(Months::new(n.checked_mul(12)?)),
+        }
+    }
+
+    /// 尚未完成的下一次日期；一次性目標逾期仍會回傳原日期，已完成則為 None
+    pub fn next_occurrence(&self, today: NaiveDate) -> Option<NaiveDate> {
+        if self.recurrence == Recurrence::Once {
+            return self.completed.is_none().then_some(self.date);
+        }
+        (0..)
+            .map_while(|n| self.occurrence(n))
+            .find(|&date| date >= today && self.completed.is_none_or(|done| date > done))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.recurrence == Recurrence::Once && self.completed.is_some()
+    }
+
+    /// 把下一次標記為完成
+    pub fn complete(&mut self, today: NaiveDate) {
+        if let Some(date) = self.next_occurrence(today) {
+            self.completed = Some(date);
+        }
+    }
+}
+
+/// 未完成目標依下一次日期排序
+pub fn upcoming(goals: &[Goal], today: NaiveDate) -> Vec<(usize, NaiveDate)> {
+    let mut upcoming: Vec<(usize, NaiveDate)> = goals
+        .iter()
+        .enumerate()
+        .filter_map(|(i, goal)| Some((i, goal.next_occurrence(today)?)))
+        .collect();
+    upcoming.sort_by_key(|&(_, date)| date);
+    upcoming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn one_off_goal_stays_until_completed() {
+        let mut goal = Goal::new("畢業".into(), date(2024, 6, 1), Recurrence::Once);
+        assert_eq!(goal.next_occurrence(date(2024, 7, 1)), Some(date(2024, 6, 1)));
+        goal.complete(date(2024, 7, 1));
+        assert!(goal.is_done());
+        assert_eq!(goal.next_occurrence(date(2024, 7, 1)), None);
+    }
+
+    #[test]
+    fn monthly_goal_clamps_to_month_end() {
+        let goal = Goal::new("繳費".into(), date(2024, 1, 31), Recurrence::Monthly);
+        assert_eq!(goal.next_occurrence(date(2024, 2, 1)), Some(date(2024, 2, 29)));
+        assert_eq!(goal.next_occurrence(date(2024, 4, 1)), Some(date(2024, 4, 30)));
+    }
+
+    #[test]
+    fn completing_recurring_goal_moves_to_next_occurrence() {
+        let mut goal = Goal::new("紀念日".into(), date(2020, 3, 10), Recurrence::Yearly);
+        let today = date(2024, 3, 10);
+        assert_eq!(goal.next_occurrence(today), Some(date(2024, 3, 10)));
+        goal.complete(today);
+        assert_eq!(goal.next_occurrence(today), Some(date(2025, 3, 10)));
+        assert!(!goal.is_done());
+    }
+}
@@ -0,0 +1,305 @@
+//! 介面語言：原始碼中的字串即繁體中文，顯示前依目前語言查表換成簡體中文或英文
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    TraditionalChinese,
+    SimplifiedChinese,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::TraditionalChinese, Language::SimplifiedChinese, Language::English];
+
+    /// 以該語言本身書寫的名稱
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::TraditionalChinese => "繁體中文",
+            Language::SimplifiedChinese => "简体中文",
+            Language::English => "English",
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::SimplifiedChinese,
+        2 => Language::English,
+        _ => Language::TraditionalChinese,
+    }
+}
+
+/// 以 `language` 查表；表中沒有的字串原樣顯示
+pub fn translate(language: Language, text: &'static str) -> &'static str {
+    let column = match language {
+        Language::TraditionalChinese => return text,
+        Language::SimplifiedChinese => 0,
+        Language::English => 1,
+    };
+    TABLE
+        .iter()
+        .find(|(key, _)| *key == text)
+        .map_or(text, |(_, translations)| translations[column])
+}
+
+/// 以目前語言翻譯
+pub fn tr(text: &'static str) -> &'static str {
+    translate(language(), text)
+}
+
+/// 把 `args` 填入模板：`{}` 依序取用，`{0}`、`{1}` 指定位置，供語序不同的翻譯使用
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out += &rest[..start];
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        let index = match rest[start + 1..end].parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => {
+                next += 1;
+                next - 1
+            }
+        };
+        if let Some(arg) = args.get(index) {
+            out += &arg.to_string();
+        }
+        rest = &rest[end + 1..];
+    }
+    out + rest
+}
+
+/// 翻譯後填入參數；原文模板只使用 `{}`，數字格式請先自行處理
+#[macro_export]
+macro_rules! tr {
+    ($text:literal) => {
+        $crate::i18n::tr($text)
+    };
+    ($text:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::tr($text), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+
+/// 繁體中文 → [簡體中文, 英文]
+const TABLE: &[(&str, [&str; 2])] = &[
+    // 設置頁與設置面板
+    ("🎂 人生倒數計時設置", ["🎂 人生倒数计时设置", "🎂 Life Countdown Setup"]),
+    ("姓名:", ["姓名:", "Name:"]),
+    ("生日:", ["生日:", "Birthday:"]),
+    ("年:", ["年:", "Year:"]),
+    ("月:", ["月:", "Month:"]),
+    ("日:", ["日:", "Day:"]),
+    ("性別:", ["性别:", "Gender:"]),
+    ("男", ["男", "Male"]),
+    ("女", ["女", "Female"]),
+    ("其他", ["其他", "Other"]),
+    ("✅ 完成設置", ["✅ 完成设置", "✅ Done"]),
+    ("國家/地區:", ["国家/地区:", "Country/region:"]),
+    ("模型估計壽命: {} 歲", ["模型估计寿命: {} 岁", "Model estimate: {} years"]),
+    ("依各國出生時平均餘命以 Gompertz 模型配適的估計，並非官方生命表", ["依各国出生时平均余命以 Gompertz 模型拟合的估计，并非官方生命表", "Estimated with a Gompertz model fitted to each country's life expectancy at birth, not an official life table"]),
+    ("手動設定預期壽命", ["手动设定预期寿命", "Set life expectancy manually"]),
+    (" 歲", [" 岁", " years"]),
+    ("設置", ["设置", "Settings"]),
+    ("⚙️設置", ["⚙️设置", "⚙️Settings"]),
+    ("重新設定生日", ["重新设定生日", "Change birthday"]),
+    ("保存設置", ["保存设置", "Save settings"]),
+    ("🌐 語言:", ["🌐 语言:", "🌐 Language:"]),
+    ("朋友", ["朋友", "friend"]),
+    // 人資料
+    ("➕ 新增人資料", ["➕ 新增人资料", "➕ New profile"]),
+    ("🗑 刪除此人資料", ["🗑 删除此人资料", "🗑 Delete this profile"]),
+    ("👥 比較", ["👥 比较", "👥 Compare"]),
+    ("姓名", ["姓名", "Name"]),
+    ("年齡", ["年龄", "Age"]),
+    ("已度過", ["已度过", "Lived"]),
+    ("剩餘", ["剩余", "Remaining"]),
+    ("預期壽命", ["预期寿命", "Life expectancy"]),
+    ("人生進度", ["人生进度", "Progress"]),
+    ("下一個目標", ["下一个目标", "Next goal"]),
+    ("尚未設定生日", ["尚未设定生日", "No birthday set"]),
+    ("{} 歲", ["{} 岁", "{} years"]),
+    ("{} 天", ["{} 天", "{} days"]),
+    ("{}（{} 天後）", ["{}（{} 天后）", "{} (in {} days)"]),
+    // 主頁
+    ("👋 {}，還剩 {} 天", ["👋 {}，还剩 {} 天", "👋 {}, {} days left"]),
+    ("天", ["天", "days"]),
+    ("剩餘時間", ["剩余时间", "remaining"]),
+    ("人生進度: {}%", ["人生进度: {}%", "Life progress: {}%"]),
+    ("📊 統計訊息", ["📊 统计信息", "📊 Statistics"]),
+    ("當前年齡: {} 歲", ["当前年龄: {} 岁", "Current age: {} years"]),
+    ("已度過: {} 天", ["已度过: {} 天", "Days lived: {}"]),
+    ("預期壽命: {} 歲", ["预期寿命: {} 岁", "Life expectancy: {} years"]),
+    ("剩餘: {} 年 {} 月 {} 天 {} 小時", ["剩余: {} 年 {} 月 {} 天 {} 小时", "Remaining: {} years {} months {} days {} hours"]),
+    ("{} 年 {} 月 {} 天 {}", ["{} 年 {} 月 {} 天 {}", "{}y {}m {}d {}"]),
+    ("⏱ 即時秒數", ["⏱ 实时秒数", "⏱ Live seconds"]),
+    ("💭 今日思考", ["💭 今日思考", "💭 Thought of the day"]),
+    ("⚠️ 珍惜時光", ["⚠️ 珍惜时光", "⚠️ Cherish your time"]),
+    ("⏰ 時間不多", ["⏰ 时间不多", "⏰ Time is running short"]),
+    ("📔 日記", ["📔 日记", "📔 Journal"]),
+    // 命令列
+    ("不明的參數: {}", ["不明的参数: {}", "Unknown argument: {}"]),
+    ("不明的指令: {}", ["不明的指令: {}", "Unknown command: {}"]),
+    ("--birthday 需要日期", ["--birthday 需要日期", "--birthday needs a date"]),
+    ("日期格式錯誤，請使用 YYYY-MM-DD: {}", ["日期格式错误，请使用 YYYY-MM-DD: {}", "Invalid date, use YYYY-MM-DD: {}"]),
+    ("用法:\n  life_countdown                        開啟視窗\n  life_countdown status [--json]        顯示剩餘時間\n  life_countdown set --birthday YYYY-MM-DD  設定生日", [
+        "用法:\n  life_countdown                        打开窗口\n  life_countdown status [--json]        显示剩余时间\n  life_countdown set --birthday YYYY-MM-DD  设定生日",
+        "Usage:\n  life_countdown                        open the window\n  life_countdown status [--json]        show the remaining time\n  life_countdown set --birthday YYYY-MM-DD  set the birthday",
+    ]),
+    ("{}：剩餘 {} 天 · 人生進度 {}% · {} 歲", ["{}：剩余 {} 天 · 人生进度 {}% · {} 岁", "{}: {} days left · {}% of life · age {}"]),
+    ("尚未設定生日，請先執行 life_countdown set --birthday YYYY-MM-DD", ["尚未设定生日，请先执行 life_countdown set --birthday YYYY-MM-DD", "No birthday set; run life_countdown set --birthday YYYY-MM-DD first"]),
+    ("生日不能晚於今天: {}", ["生日不能晚于今天: {}", "The birthday cannot be in the future: {}"]),
+    ("已將 {} 的生日設為 {}", ["已将 {} 的生日设为 {}", "Set the birthday of {} to {}"]),
+    // 目標
+    ("🎯 目標與里程碑", ["🎯 目标与里程碑", "🎯 Goals & milestones"]),
+    ("今天", ["今天", "Today"]),
+    ("剩餘 {} 天", ["剩余 {} 天", "{} days left"]),
+    ("今天是「{}」", ["今天是「{}」", "Today: {}"]),
+    ("距離「{}」還有 {} 天", ["距离「{}」还有 {} 天", "{1} days until {0}"]),
+    ("「{}」已逾期 {} 天", ["「{}」已逾期 {} 天", "{} is {} days overdue"]),
+    (" · 位於剩餘時間的 {}%", [" · 位于剩余时间的 {}%", " · {}% into your remaining time"]),
+    ("刪除", ["删除", "Delete"]),
+    ("✅ 完成", ["✅ 完成", "✅ Done"]),
+    ("已完成 ({})", ["已完成 ({})", "Completed ({})"]),
+    ("名稱:", ["名称:", "Title:"]),
+    ("日期:", ["日期:", "Date:"]),
+    ("➕ 新增", ["➕ 新增", "➕ Add"]),
+    ("不重複", ["不重复", "Once"]),
+    ("每週", ["每周", "Weekly"]),
+    ("每月", ["每月", "Monthly"]),
+    ("每年", ["每年", "Yearly"]),
+    // 人生週曆
+    ("🗓 人生週曆", ["🗓 人生周历", "🗓 Life in weeks"]),
+    ("每格:", ["每格:", "Each cell:"]),
+    ("週", ["周", "Week"]),
+    ("月", ["月", "Month"]),
+    ("🖼 匯出 PNG", ["🖼 导出 PNG", "🖼 Export PNG"]),
+    ("{} 歲，第 {} {}", ["{} 岁，第 {} {}", "Age {0}, {2} {1}"]),
+    ("（{} 起）", ["（{} 起）", " (from {})"]),
+    (" ↓ 超出格子範圍", [" ↓ 超出格子范围", " ↓ beyond the grid"]),
+    ("無法匯出圖片: {}", ["无法导出图片: {}", "Cannot export image: {}"]),
+    // 日記
+    ("{} 年 {} 月", ["{} 年 {} 月", "{} / {}"]),
+    ("一", ["一", "Mo"]),
+    ("二", ["二", "Tu"]),
+    ("三", ["三", "We"]),
+    ("四", ["四", "Th"]),
+    ("五", ["五", "Fr"]),
+    ("六", ["六", "Sa"]),
+    ("日", ["日", "Su"]),
+    ("心情:", ["心情:", "Mood:"]),
+    ("標籤:", ["标签:", "Tags:"]),
+    ("旅行, 家人", ["旅行, 家人", "travel, family"]),
+    ("💾 保存", ["💾 保存", "💾 Save"]),
+    ("🗑 刪除", ["🗑 删除", "🗑 Delete"]),
+    ("📅 往年今日", ["📅 往年今日", "📅 On this day"]),
+    ("{} 年前（{}）{}", ["{} 年前（{}）{}", "{} years ago ({}) {}"]),
+    // 名言
+    ("💭 名言", ["💭 名言", "💭 Quotes"]),
+    ("輪換:", ["轮换:", "Rotation:"]),
+    ("每小時", ["每小时", "Hourly"]),
+    ("每天", ["每天", "Daily"]),
+    ("每次啟動隨機", ["每次启动随机", "Random per launch"]),
+    ("分類:", ["分类:", "Category:"]),
+    ("全部", ["全部", "All"]),
+    ("未分類", ["未分类", "Uncategorised"]),
+    ("只顯示收藏", ["只显示收藏", "Favourites only"]),
+    ("收藏", ["收藏", "Add to favourites"]),
+    ("取消收藏", ["取消收藏", "Remove from favourites"]),
+    ("名言檔案: {}", ["名言文件: {}", "Quote file: {}"]),
+    ("🔄 重新載入名言", ["🔄 重新载入名言", "🔄 Reload quotes"]),
+    ("還沒有名言，請編輯 {}", ["还没有名言，请编辑 {}", "No quotes yet, edit {}"]),
+    ("無法讀取名言檔案 {}: {}", ["无法读取名言文件 {}: {}", "Cannot read quote file {}: {}"]),
+    ("無法建立名言檔案 {}: {}", ["无法创建名言文件 {}: {}", "Cannot create quote file {}: {}"]),
+    // 匯入匯出與儲存
+    ("📦 匯入/匯出", ["📦 导入/导出", "📦 Import/export"]),
+    ("匯出 JSON", ["导出 JSON", "Export JSON"]),
+    ("匯出 CSV", ["导出 CSV", "Export CSV"]),
+    ("📅 匯出里程碑 (.ics)", ["📅 导出里程碑 (.ics)", "📅 Export milestones (.ics)"]),
+    ("匯入檔案 (.json / .csv):", ["导入文件 (.json / .csv):", "Import file (.json / .csv):"]),
+    ("合併", ["合并", "Merge"]),
+    ("覆蓋全部", ["覆盖全部", "Overwrite all"]),
+    ("匯入", ["导入", "Import"]),
+    ("已匯出到 {}", ["已导出到 {}", "Exported to {}"]),
+    ("已匯入：新增 {} 個目標、{} 篇日記", ["已导入：新增 {} 个目标、{} 篇日记", "Imported {} goals and {} journal entries"]),
+    ("⏳ {} 的預期壽命終點", ["⏳ {} 的预期寿命终点", "⏳ End of {}'s life expectancy"]),
+    ("找不到系統設定目錄，設定將無法保存", ["找不到系统配置目录，设置将无法保存", "No system config directory found; settings cannot be saved"]),
+    ("保存失敗: {}", ["保存失败: {}", "Save failed: {}"]),
+    ("設定檔版本 {} 無法辨識，可能由較新的版本寫入", ["配置文件版本 {} 无法识别，可能由较新的版本写入", "Unknown config version {}; it may have been written by a newer release"]),
+    ("設定檔格式錯誤: {}", ["配置文件格式错误: {}", "Malformed config file: {}"]),
+    ("設定檔內容錯誤: {}", ["配置文件内容错误: {}", "Invalid config contents: {}"]),
+    ("無法讀取 {}: {}", ["无法读取 {}: {}", "Cannot read {}: {}"]),
+    ("無法寫入 {}: {}", ["无法写入 {}: {}", "Cannot write {}: {}"]),
+    ("無法建立 {}: {}", ["无法创建 {}: {}", "Cannot create {}: {}"]),
+    ("無法更新 {}: {}", ["无法更新 {}: {}", "Cannot replace {}: {}"]),
+    ("{}；已改用備份 {}", ["{}；已改用备份 {}", "{}; restored from backup {}"]),
+    ("{}；原檔已移到 {}", ["{}；原文件已移到 {}", "{}; the original file was moved to {}"]),
+    ("{}；為避免覆蓋，已停用保存", ["{}；为避免覆盖，已停用保存", "{}; saving is disabled so the file is not overwritten"]),
+    ("{}；{}，為避免覆蓋，已停用保存", ["{}；{}，为避免覆盖，已停用保存", "{}; {}, saving is disabled so the file is not overwritten"]),
+    ("設定檔已在其他地方被修改，為避免覆蓋未保存", ["配置文件已在其他地方被修改，为避免覆盖未保存", "The config file was changed elsewhere; not saved to avoid overwriting it"]),
+    ("設定檔已在其他地方被修改；同一份人資料兩邊都有變更，保留了這裡的版本", ["配置文件已在其他地方被修改；同一份资料两边都有更改，保留了这里的版本", "The config file was changed elsewhere; a profile changed on both sides keeps the version from this window"]),
+    ("設定檔已在其他地方更新，已與這裡的變更合併", ["配置文件已在其他地方更新，已与这里的更改合并", "The config file was updated elsewhere and has been merged with the changes here"]),
+    ("無法移開損毀的設定檔: {}", ["无法移开损坏的配置文件: {}", "Cannot move the corrupt config file aside: {}"]),
+    ("無法序列化設定: {}", ["无法序列化设置: {}", "Cannot serialise settings: {}"]),
+    ("無法輪替備份: {}", ["无法轮替备份: {}", "Cannot rotate backups: {}"]),
+    ("無法建立備份: {}", ["无法创建备份: {}", "Cannot create backup: {}"]),
+    ("無法序列化資料: {}", ["无法序列化数据: {}", "Cannot serialise data: {}"]),
+    ("CSV 是空的", ["CSV 是空的", "The CSV file is empty"]),
+    ("CSV 缺少欄位 {}", ["CSV 缺少字段 {}", "The CSV file is missing column {}"]),
+    ("第 {} 列日期格式錯誤: {}", ["第 {} 行日期格式错误: {}", "Line {}: invalid date {}"]),
+    ("第 {} 列缺少日期", ["第 {} 行缺少日期", "Line {}: missing date"]),
+    ("第 {} 列類型不明: {}", ["第 {} 行类型不明: {}", "Line {}: unknown type {}"]),
+    ("CSV 沒有任何人資料", ["CSV 没有任何人资料", "The CSV file contains no profiles"]),
+    // 生命表的國家名稱
+    ("全球", ["全球", "World"]),
+    ("台灣", ["台湾", "Taiwan"]),
+    ("中國", ["中国", "China"]),
+    ("香港", ["香港", "Hong Kong"]),
+    ("日本", ["日本", "Japan"]),
+    ("韓國", ["韩国", "South Korea"]),
+    ("新加坡", ["新加坡", "Singapore"]),
+    ("美國", ["美国", "United States"]),
+    ("英國", ["英国", "United Kingdom"]),
+    ("德國", ["德国", "Germany"]),
+    ("法國", ["法国", "France"]),
+    ("澳洲", ["澳大利亚", "Australia"]),
+    ("加拿大", ["加拿大", "Canada"]),
+    ("馬來西亞", ["马来西亚", "Malaysia"]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_keys_are_unique_and_keep_placeholders() {
+        for (i, (key, translations)) in TABLE.iter().enumerate() {
+            assert!(TABLE[..i].iter().all(|(other, _)| other != key), "duplicate key {}", key);
+            for translation in translations {
+                assert_eq!(key.matches('{').count(), translation.matches('{').count(), "{}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn translates_and_fills_templates() {
+        assert_eq!(translate(Language::English, "設置"), "Settings");
+        assert_eq!(translate(Language::SimplifiedChinese, "設置"), "设置");
+        assert_eq!(translate(Language::TraditionalChinese, "設置"), "設置");
+        assert_eq!(translate(Language::English, "沒有翻譯"), "沒有翻譯");
+        let template = translate(Language::English, "距離「{}」還有 {} 天");
+        assert_eq!(fill(template, &[&"Trip", &12]), "12 days until Trip");
+    }
+}
@@ -0,0 +1,134 @@
+//! 每日日記：每個日期一篇，含心情評分與標籤
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub type Journal = BTreeMap<NaiveDate, JournalEntry>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub text: String,
+    /// 1（很差）到 5（很好）
+    pub mood: u8,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+pub const MOODS: [&str; 5] = ["😞", "🙁", "😐", "🙂", "😄"];
+
+impl JournalEntry {
+    pub fn mood_icon(&self) -> &'static str {
+        MOODS[(self.mood.clamp(1, 5) - 1) as usize]
+    }
+}
+
+/// 以逗號、空白或 `#` 分隔的標籤，去除重複
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c == '，' || c == '#' || c.is_whitespace()) {
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// 把 `date` 的日記換成編輯中的內容；內文與標籤都空白時刪除。傳回是否有變更
+pub fn apply_edit(journal: &mut Journal, date: NaiveDate, text: &str, mood: u8, tags: &str) -> bool {
+    let tags = parse_tags(tags);
+    let edited = (!text.trim().is_empty() || !tags.is_empty()).then(|| JournalEntry { text: text.to_string(), mood, tags });
+    if journal.get(&date) == edited.as_ref() {
+        return false;
+    }
+    match edited {
+        Some(entry) => journal.insert(date, entry),
+        None => journal.remove(&date),
+    };
+    true
+}
+
+/// 內文或標籤包含 `query`（不分大小寫）的日記，新的在前
+pub fn search<'a>(journal: &'a Journal, query: &str) -> Vec<(NaiveDate, &'a JournalEntry)> {
+    let query = query.trim().trim_start_matches('#').to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    journal
+        .iter()
+        .rev()
+        .filter(|(_, entry)| {
+            entry.text.to_lowercase().contains(&query)
+                || entry.tags.iter().any(|tag| tag.to_lowercase().contains(&query))
+        })
+        .map(|(&date, entry)| (date, entry))
+        .collect()
+}
+
+/// 往年同一天的日記，近的在前；2 月 29 日的日記在平年的 2 月 28 日出現
+pub fn on_this_day(journal: &Journal, today: NaiveDate) -> Vec<(i32, NaiveDate, &JournalEntry)> {
+    journal
+        .iter()
+        .rev()
+        .filter(|(date, _)| date.year() < today.year())
+        .filter(|(date, _)| {
+            let same_day = (date.month(), date.day()) == (today.month(), today.day());
+            let leap_day = (date.month(), date.day()) == (2, 29)
+                && (today.month(), today.day()) == (2, 28)
+                && NaiveDate::from_ymd_opt(today.year(), 2, 29).is_none();
+            same_day || leap_day
+        })
+        .map(|(&date, entry)| (today.year() - date.year(), date, entry))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn entry(text: &str, tags: &str) -> JournalEntry {
+        JournalEntry { text: text.into(), mood: 3, tags: parse_tags(tags) }
+    }
+
+    #[test]
+    fn parses_tags_with_mixed_separators() {
+        assert_eq!(parse_tags("#旅行, 家人，旅行  work"), ["旅行", "家人", "work"]);
+    }
+
+    #[test]
+    fn edits_only_change_the_journal_when_they_differ() {
+        let mut journal = Journal::new();
+        assert!(!apply_edit(&mut journal, date(2024, 1, 1), "  ", 5, ""));
+        assert!(apply_edit(&mut journal, date(2024, 1, 1), "散步", 4, "戶外, 家人"));
+        assert!(!apply_edit(&mut journal, date(2024, 1, 1), "散步", 4, "#戶外 家人"));
+        assert!(apply_edit(&mut journal, date(2024, 1, 1), "", 4, ""));
+        assert!(journal.is_empty());
+    }
+
+    #[test]
+    fn searches_text_and_tags() {
+        let mut journal = Journal::new();
+        journal.insert(date(2023, 1, 1), entry("New year walk", "outdoor"));
+        journal.insert(date(2023, 2, 1), entry("讀書", "#Reading"));
+        journal.insert(date(2023, 3, 1), entry("Walking again", ""));
+        let dates: Vec<_> = search(&journal, "walk").into_iter().map(|(d, _)| d).collect();
+        assert_eq!(dates, [date(2023, 3, 1), date(2023, 1, 1)]);
+        assert_eq!(search(&journal, "#reading").len(), 1);
+        assert!(search(&journal, "  ").is_empty());
+    }
+
+    #[test]
+    fn finds_entries_from_previous_years() {
+        let mut journal = Journal::new();
+        journal.insert(date(2020, 2, 29), entry("閏日", ""));
+        journal.insert(date(2022, 2, 28), entry("兩年前", ""));
+        journal.insert(date(2023, 2, 28), entry("今年", ""));
+        let found: Vec<_> = on_this_day(&journal, date(2023, 2, 28)).into_iter().map(|(years, _, _)| years).collect();
+        assert_eq!(found, [1, 3]);
+        assert!(on_this_day(&journal, date(2024, 2, 28)).iter().all(|&(_, d, _)| d.day() == 28));
+    }
+}
@@ -0,0 +1,130 @@
+//! 內建的簡易生命表，依國家與性別查詢某年齡的平均餘命
+//!
+//! 資料並非官方公布的生命表，而是依各國出生時平均餘命配適 Gompertz 死亡率模型得出的模型估計，
+//! 介面上也標示為模型估計，僅供參考。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::profile::Gender;
+
+/// 找不到國家時使用的全球平均
+pub const DEFAULT_COUNTRY: &str = "WLD";
+
+const DATA: &str = include_str!("../assets/life_table.csv");
+
+struct Table {
+    /// 所有國家（代碼, 名稱），依資料檔順序
+    countries: Vec<(&'static str, &'static str)>,
+    /// 依（國家代碼, 是否女性）分組的（年齡, 平均餘命），年齡遞增
+    series: HashMap<(&'static str, bool), Vec<(f32, f32)>>,
+}
+
+/// 解析資料檔；欄位不足或數字無法解析的行直接略過
+fn parse(text: &'static str) -> Table {
+    let mut table = Table { countries: Vec::new(), series: HashMap::new() };
+    for line in text.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [code, name, sex, age, remaining] = fields[..] else {
+            continue;
+        };
+        let (Ok(age), Ok(remaining)) = (age.parse(), remaining.parse()) else {
+            continue;
+        };
+        if table.countries.last().map(|&(last, _)| last) != Some(code) {
+            table.countries.push((code, name));
+        }
+        table.series.entry((code, sex == "F")).or_default().push((age, remaining));
+    }
+    for series in table.series.values_mut() {
+        series.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    table
+}
+
+/// 內建資料只在第一次使用時解析
+fn table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+    TABLE.get_or_init(|| parse(DATA))
+}
+
+/// 所有可選的國家（代碼, 名稱），依資料檔順序
+pub fn countries() -> &'static [(&'static str, &'static str)] {
+    &table().countries
+}
+
+/// 國家代碼對應的名稱；未知代碼顯示為全球平均
+pub fn country_name(code: &str) -> &'static str {
+    let find = |code: &str| countries().iter().find(|&&(c, _)| c == code).map(|&(_, name)| name);
+    find(code).or_else(|| find(DEFAULT_COUNTRY)).unwrap_or("")
+}
+
+/// 單一性別在 `age` 歲時的平均餘命，於表列年齡間線性內插
+fn remaining_for(code: &str, female: bool, age: f32) -> Option<f32> {
+    let series = table().series.get(&(code, female))?;
+    let upper = series.partition_point(|&(listed, _)| listed <= age);
+    if upper == series.len() {
+        return series.last().map(|&(_, remaining)| remaining);
+    }
+    if upper == 0 {
+        return Some(series[0].1);
+    }
+    let ((a_age, a), (b_age, b)) = (series[upper - 1], series[upper]);
+    let t = (age - a_age) / (b_age - a_age);
+    Some(a + (b - a) * t)
+}
+
+/// 已活到 `age` 歲的人，預期還能再活的年數；「其他」取男女平均
+pub fn remaining_years(country: &str, gender: &Gender, age: f32) -> f32 {
+    let code = if countries().iter().any(|&(code, _)| code == country) { country } else { DEFAULT_COUNTRY };
+    let age = age.max(0.0);
+    let lookup = |female| remaining_for(code, female, age).unwrap_or(0.0);
+    match gender {
+        Gender::Male => lookup(false),
+        Gender::Female => lookup(true),
+        Gender::Other => (lookup(false) + lookup(true)) / 2.0,
+    }
+}
+
+/// 活到 `age` 歲後的預期總壽命
+pub fn expected_lifespan(country: &str, gender: &Gender, age: f32) -> f32 {
+    age.max(0.0) + remaining_years(country, gender, age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_listed_ages() {
+        let at_30 = remaining_years("TW", &Gender::Male, 30.0);
+        let at_35 = remaining_years("TW", &Gender::Male, 35.0);
+        let between = remaining_years("TW", &Gender::Male, 32.5);
+        assert!((between - (at_30 + at_35) / 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn conditional_lifespan_grows_with_age() {
+        let mut previous = 0.0;
+        for age in (0..=110).step_by(5) {
+            let lifespan = expected_lifespan("JP", &Gender::Female, age as f32);
+            assert!(lifespan > previous);
+            previous = lifespan;
+        }
+    }
+
+    #[test]
+    fn unknown_country_falls_back_to_world() {
+        assert_eq!(remaining_years("ZZ", &Gender::Other, 40.0), remaining_years(DEFAULT_COUNTRY, &Gender::Other, 40.0));
+        assert_eq!(country_name("ZZ"), country_name(DEFAULT_COUNTRY));
+        assert!(countries().iter().any(|&(code, _)| code == "TW"));
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        let table = parse("# 註解\nAA,甲,M,0,70\nAA,甲,M\nAA,甲,F,abc,1\n\nAA,甲,F,0,75,多餘\nBB,乙,F,0,80\n");
+        assert_eq!(table.series.values().map(Vec::len).sum::<usize>(), 2);
+        assert_eq!(table.countries, [("AA", "甲"), ("BB", "乙")]);
+        assert_eq!(table.series[&("BB", true)], [(0.0, 80.0)]);
+    }
+}
@@ -1,11 +1,32 @@
 #![windows_subsystem = "windows"]
 use eframe::{egui, NativeOptions};
 use image::load_from_memory;
-use chrono::{DateTime, Local, NaiveDate, Duration};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, Weekday};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod i18n;
+mod cli;
+mod countdown;
+mod goals;
+mod journal;
+mod life_table;
+mod profile;
+mod quotes;
+mod storage;
+mod transfer;
+mod weeks;
+
+use goals::{Goal, Recurrence};
+use i18n::Language;
+use profile::{Gender, Profiles, UserData};
+use quotes::{Quote, Rotation};
+use storage::{Loaded, Storage};
+use transfer::ImportMode;
+use weeks::{GridUnit, LifeGrid};
 
 // 添加圖標相關的結構
 #[derive(Default)]
@@ -14,53 +35,53 @@ struct IconState {
     last_update: DateTime<Local>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
-struct UserData {
-    birthday: Option<NaiveDate>,
-    life_expectancy: f32,
-    name: String,
-    gender: Gender,
-    country: String,
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-enum Gender {
-    Male,
-    Female,
-    Other,
-}
-
-impl Default for Gender {
-    fn default() -> Self {
-        Gender::Male
-    }
-}
-
 #[derive(Default)]
 struct LifeCountdownApp {
+    /// 目前選取的人資料；保存時寫回 `profiles`
     user_data: UserData,
+    profiles: Profiles,
+    /// 上次讀到或寫入的設定檔內容，合併其他程式的修改時當作共同的起點
+    base: Profiles,
+    /// 上次檢查設定檔是否被其他程式修改的時間
+    disk_checked: Option<Instant>,
+    show_comparison: bool,
     current_page: Page,
     birth_year: String,
     birth_month: String,
     birth_day: String,
     show_settings: bool,
-    config_path: PathBuf,
+    storage: Storage,
+    /// 讀寫設定檔失敗時顯示在頂端的訊息
+    storage_error: Option<String>,
     current_quote: String,
-    quote_index: usize,
-    last_quote_update: DateTime<Local>,
+    quotes: Vec<Quote>,
+    quotes_path: PathBuf,
+    /// 「每次啟動隨機」使用的亂數，啟動時決定
+    launch_seed: u64,
     icon_state: IconState,
+    goal_title: String,
+    goal_date: String,
+    goal_recurrence: Recurrence,
+    grid_unit: GridUnit,
+    export_message: Option<String>,
+    /// 月曆顯示的月份（該月 1 日）與正在編輯的日期
+    journal_month: NaiveDate,
+    journal_date: NaiveDate,
+    journal_text: String,
+    journal_mood: u8,
+    journal_tags: String,
+    journal_search: String,
+    import_path: String,
+    import_mode: ImportMode,
+    transfer_message: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Default, Debug, PartialEq)]
 enum Page {
+    #[default]
     Setup,
     Main,
-}
-
-impl Default for Page {
-    fn default() -> Self {
-        Page::Setup
-    }
+    Journal,
 }
 
 
@@ -80,18 +101,23 @@ impl LifeCountdownApp {
         let mut app = Self::default();
         
         // 設置配置文件路径
-        if let Some(config_dir) = dirs::config_dir() {
-            app.config_path = config_dir.join("life_countdown").join("config.json");
+        if let Some(config_dir) = storage::config_dir() {
+            app.storage = Storage::new(config_dir.join("config.json"));
+            app.quotes_path = config_dir.join("quotes.txt");
+        } else {
+            app.storage_error = Some(tr!("找不到系統設定目錄，設定將無法保存").to_string());
         }
         
         // 加载用户数据
         app.load_user_data();
         
         // 設置默认值
-        app.user_data.life_expectancy = 75.0;
-        app.last_quote_update = Local::now();
         app.icon_state.last_update = Local::now();
-        app.current_quote = "每一天都是新的開始".to_string();
+        app.launch_seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos() as u64 ^ elapsed.as_secs());
+        app.load_quotes();
+        app.select_journal_date(Local::now().date_naive());
         
         // 如果已有生日数据，直接进入主页面
         if app.user_data.birthday.is_some() {
@@ -148,7 +174,7 @@ impl LifeCountdownApp {
         }
         
         // 繪製進度環
-        if let Some(progress) = self.calculate_life_progress() {
+        if let Some(progress) = self.user_data.calculate_life_progress() {
             let progress_angle = (progress / 100.0) * 2.0 * std::f32::consts::PI;
             
             for y in 0..size {
@@ -179,7 +205,7 @@ impl LifeCountdownApp {
     
     // 生成並設置圖標
     fn generate_and_set_icon(&mut self, ctx: &egui::Context) {
-        if let Some(remaining_days) = self.calculate_remaining_days() {
+        if let Some(remaining_days) = self.user_data.calculate_remaining_days() {
             let icon_data = self.generate_icon_data(remaining_days);
             
             let icon = egui::IconData {
@@ -198,7 +224,7 @@ impl LifeCountdownApp {
     
     // 檢查並更新圖標
     fn check_and_update_icon(&mut self, ctx: &egui::Context) {
-        if let Some(remaining_days) = self.calculate_remaining_days() {
+        if let Some(remaining_days) = self.user_data.calculate_remaining_days() {
             let now = Local::now();
             
             // 檢查是否需要更新圖標（天數改變或每小時更新一次）
@@ -213,153 +239,740 @@ impl LifeCountdownApp {
     
     
     fn load_user_data(&mut self) {
-        if let Ok(data) = fs::read_to_string(&self.config_path) {
-            if let Ok(user_data) = serde_json::from_str::<UserData>(&data) {
-                self.user_data = user_data;
+        match self.storage.load() {
+            Ok(loaded) => {
+                if let Some(profiles) = loaded.profiles {
+                    self.user_data = profiles.active().clone();
+                    self.base = profiles.clone();
+                    self.profiles = profiles;
+                }
+                i18n::set_language(self.profiles.language);
+                self.storage_error = loaded.warning;
             }
+            Err(e) => self.storage_error = Some(e),
         }
     }
 
-    fn save_user_data(&self) {
-        if let Some(parent) = self.config_path.parent() {
-            let _ = fs::create_dir_all(parent);
+    /// 設定檔被其他程式（例如命令列的 `set`）改過時讀進來，與這裡尚未保存的變更合併，
+    /// 傳回是否有讀到新的內容；編輯中的日記與設置頁欄位不受影響
+    fn merge_external_changes(&mut self) -> bool {
+        if !self.storage.changed_on_disk() {
+            return false;
         }
-        
-        if let Ok(data) = serde_json::to_string_pretty(&self.user_data) {
-            let _ = fs::write(&self.config_path, data);
+        self.profiles.profiles[self.profiles.active] = self.user_data.clone();
+        let theirs = match self.storage.load() {
+            Ok(Loaded { profiles: Some(theirs), warning }) => {
+                self.storage_error = warning.or(self.storage_error.take());
+                theirs
+            }
+            // 檔案被刪除時保留這裡的內容，下次保存會重新寫入
+            Ok(Loaded { profiles: None, warning }) => {
+                self.storage_error = warning.or(self.storage_error.take());
+                return false;
+            }
+            Err(e) => {
+                self.storage_error = Some(e);
+                return false;
+            }
+        };
+        let (merged, conflict) = self.profiles.merge(&self.base, theirs.clone());
+        self.base = theirs;
+        self.profiles = merged;
+        self.user_data = self.profiles.active().clone();
+        i18n::set_language(self.profiles.language);
+        if conflict {
+            self.storage_error = Some(tr!("設定檔已在其他地方被修改；同一份人資料兩邊都有變更，保留了這裡的版本").to_string());
+        }
+        if self.user_data.birthday.is_some() && self.current_page == Page::Setup {
+            self.current_page = Page::Main;
         }
+        true
     }
 
-    fn calculate_days_lived(&self) -> Option<i64> {
-        if let Some(birthday) = self.user_data.birthday {
-            let now = Local::now().date_naive();
-            let days_lived = (now - birthday).num_days();
-            Some(days_lived)
-        } else {
-            None
+    fn save_user_data(&mut self) {
+        let merged = self.merge_external_changes();
+        self.profiles.profiles[self.profiles.active] = self.user_data.clone();
+        match self.storage.save(&self.profiles) {
+            Ok(()) => {
+                self.base = self.profiles.clone();
+                if !merged {
+                    self.storage_error = None;
+                }
+            }
+            Err(e) => self.storage_error = Some(tr!("保存失敗: {}", e)),
         }
     }
 
-    fn calculate_remaining_days(&self) -> Option<i64> {
-        if let Some(birthday) = self.user_data.birthday {
-            let now = Local::now().date_naive();
-            let expected_death_date = birthday + Duration::days((self.user_data.life_expectancy * 365.25) as i64);
-            let remaining_days = (expected_death_date - now).num_days();
-            Some(remaining_days.max(0))
-        } else {
-            None
+    /// 讀寫設定檔的錯誤橫幅，可手動關閉
+    fn show_storage_error(&mut self, ctx: &egui::Context) {
+        let Some(message) = &self.storage_error else {
+            return;
+        };
+        let mut dismissed = false;
+        egui::TopBottomPanel::top("storage_error")
+            .frame(egui::Frame::none().fill(egui::Color32::from_rgb(120, 30, 30)).inner_margin(8.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::WHITE, format!("⚠️ {}", message));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        dismissed = ui.button("✖").clicked();
+                    });
+                });
+            });
+        if dismissed {
+            self.storage_error = None;
         }
     }
 
-    fn calculate_life_progress(&self) -> Option<f32> {
-        if let Some(birthday) = self.user_data.birthday {
-            let now = Local::now().date_naive();
-            let days_lived = (now - birthday).num_days() as f32;
-            let total_expected_days = self.user_data.life_expectancy * 365.25;
-            Some((days_lived / total_expected_days * 100.0).min(100.0))
-        } else {
-            None
+    /// 保存目前的人資料後切換到第 `index` 份；保存時合併了其他程式的修改，位置可能改變，因此以 `id` 找回
+    fn switch_profile(&mut self, index: usize) {
+        let id = self.profiles.profiles[index].id.clone();
+        self.commit_journal_edit();
+        self.save_user_data();
+        if let Some(index) = self.profiles.position(&id) {
+            self.profiles.active = index;
         }
+        self.show_active_profile();
     }
 
-    fn get_age_in_years(&self) -> Option<f32> {
-        if let Some(birthday) = self.user_data.birthday {
-            let now = Local::now().date_naive();
-            let years = (now - birthday).num_days() as f32 / 365.25;
-            Some(years)
-        } else {
-            None
+    /// 顯示 `profiles.active` 指向的人資料，並把編輯欄位換成它的內容；不寫回任何編輯中的內容
+    fn show_active_profile(&mut self) {
+        self.user_data = self.profiles.active().clone();
+        self.show_settings = false;
+        self.current_page = if self.user_data.birthday.is_some() { Page::Main } else { Page::Setup };
+        self.birth_year.clear();
+        self.birth_month.clear();
+        self.birth_day.clear();
+        self.select_journal_date(Local::now().date_naive());
+    }
+
+    fn add_profile(&mut self) {
+        self.profiles.profiles.push(UserData::default());
+        self.switch_profile(self.profiles.profiles.len() - 1);
+    }
+
+    fn remove_active_profile(&mut self) {
+        if self.profiles.profiles.len() > 1 {
+            // 編輯中的日記屬於被刪除的人資料，直接捨棄，不能寫進下一份
+            self.profiles.profiles.remove(self.profiles.active);
+            self.profiles.active = self.profiles.active.min(self.profiles.profiles.len() - 1);
+            self.show_active_profile();
+            self.save_user_data();
+            self.show_comparison &= self.profiles.profiles.len() > 1;
         }
     }
 
-    fn update_quote(&mut self) {
-        let quotes = [
-            "每一天都是新的開始",
-            "時間是最寶貴的資源",
-            "珍惜當下，活在此刻",
-            "生命的意義在於創造價值",
-            "不要等待機會，要創造機會",
-            "今天是你餘生的第一天",
-            "時間不會回頭，所以要向前看",
-            "每一刻都是禮物，這就是為什麼叫現在",
-        ];
-        
-        let now = Local::now();
-        let duration_since_last_update = now - self.last_quote_update;
-        
-        if duration_since_last_update.num_hours() >= 1 {
-            self.quote_index = (self.quote_index + 1) % quotes.len();
-            self.current_quote = quotes[self.quote_index].to_string();
-            self.last_quote_update = now;
+    /// 匯出檔案放在文件資料夾，找不到時放在家目錄
+    fn export_dir() -> PathBuf {
+        dirs::document_dir().or_else(dirs::home_dir).unwrap_or_default()
+    }
+
+    /// 設置面板中的匯入匯出
+    fn show_transfer_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(tr!("📦 匯入/匯出")).show(ui, |ui| {
+            let mut export = None;
+            ui.horizontal(|ui| {
+                if ui.button(tr!("匯出 JSON")).clicked() {
+                    export = Some("life_countdown_export.json");
+                }
+                if ui.button(tr!("匯出 CSV")).clicked() {
+                    export = Some("life_countdown_export.csv");
+                }
+            });
+            if let Some(name) = export {
+                self.save_user_data();
+                let path = Self::export_dir().join(name);
+                self.transfer_message = Some(match transfer::export(&self.profiles, &path) {
+                    Ok(()) => tr!("已匯出到 {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+
+            if ui.button(tr!("📅 匯出里程碑 (.ics)")).clicked() {
+                let path = Self::export_dir().join("life_countdown.ics");
+                self.transfer_message = Some(match fs::write(&path, transfer::to_ics(&self.user_data)) {
+                    Ok(()) => tr!("已匯出到 {}", path.display()),
+                    Err(e) => tr!("無法寫入 {}: {}", path.display(), e),
+                });
+            }
+
+            ui.separator();
+            ui.label(tr!("匯入檔案 (.json / .csv):"));
+            ui.text_edit_singleline(&mut self.import_path);
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.import_mode, ImportMode::Merge, tr!("合併"));
+                ui.radio_value(&mut self.import_mode, ImportMode::Overwrite, tr!("覆蓋全部"));
+            });
+            if ui.add_enabled(!self.import_path.trim().is_empty(), egui::Button::new(tr!("匯入"))).clicked() {
+                self.transfer_message = Some(match transfer::import(std::path::Path::new(self.import_path.trim())) {
+                    Ok(imported) => {
+                        self.commit_journal_edit();
+                        self.save_user_data();
+                        let (goals, entries) = transfer::apply(&mut self.profiles, imported, self.import_mode);
+                        self.user_data = self.profiles.active().clone();
+                        self.select_journal_date(Local::now().date_naive());
+                        self.save_user_data();
+                        if self.user_data.birthday.is_none() {
+                            self.current_page = Page::Setup;
+                        }
+                        tr!("已匯入：新增 {} 個目標、{} 篇日記", goals, entries)
+                    }
+                    Err(e) => e,
+                });
+            }
+
+            if let Some(message) = &self.transfer_message {
+                ui.label(message);
+            }
+        });
+    }
+
+    /// 人資料下拉選單與比較按鈕
+    fn show_profile_switcher(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        egui::ComboBox::from_id_salt("profile")
+            .selected_text(format!("👤 {}", self.user_data.display_name()))
+            .show_ui(ui, |ui| {
+                for (index, profile) in self.profiles.profiles.iter().enumerate() {
+                    let name = if index == self.profiles.active { &self.user_data } else { profile }.display_name();
+                    if ui.selectable_label(index == self.profiles.active, name).clicked() {
+                        selected = Some(index);
+                    }
+                }
+                ui.separator();
+                if ui.button(tr!("➕ 新增人資料")).clicked() {
+                    selected = Some(self.profiles.profiles.len());
+                }
+            });
+        match selected {
+            Some(index) if index == self.profiles.profiles.len() => self.add_profile(),
+            Some(index) if index != self.profiles.active => self.switch_profile(index),
+            _ => {}
+        }
+        if self.profiles.profiles.len() > 1 {
+            ui.toggle_value(&mut self.show_comparison, tr!("👥 比較"));
         }
     }
 
+    /// 並列每份人資料的統計
+    fn show_comparison_window(&mut self, ctx: &egui::Context) {
+        let today = Local::now().date_naive();
+        let mut open = self.show_comparison;
+        egui::Window::new(tr!("👥 比較")).open(&mut open).resizable(true).show(ctx, |ui| {
+            egui::Grid::new("comparison").striped(true).spacing([16.0, 6.0]).show(ui, |ui| {
+                for header in [tr!("姓名"), tr!("年齡"), tr!("已度過"), tr!("剩餘"), tr!("預期壽命"), tr!("人生進度"), tr!("下一個目標")] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for (index, profile) in self.profiles.profiles.iter().enumerate() {
+                    let profile = if index == self.profiles.active { &self.user_data } else { profile };
+                    ui.label(profile.display_name());
+                    let Some(age) = profile.get_age_in_years() else {
+                        ui.label(tr!("尚未設定生日"));
+                        ui.end_row();
+                        continue;
+                    };
+                    ui.label(tr!("{} 歲", format!("{:.1}", age)));
+                    ui.label(tr!("{} 天", profile.calculate_days_lived().unwrap_or(0)));
+                    ui.label(tr!("{} 天", profile.calculate_remaining_days().unwrap_or(0)));
+                    ui.label(tr!("{} 歲", format!("{:.1}", profile.effective_life_expectancy())));
+                    let progress = profile.calculate_life_progress().unwrap_or(0.0);
+                    ui.add(egui::ProgressBar::new(progress / 100.0).text(format!("{:.1}%", progress)).desired_width(120.0));
+                    match profile.next_goal(today) {
+                        Some((goal, date)) => ui.label(tr!("{}（{} 天後）", goal.title, (date - today).num_days())),
+                        None => ui.label("—"),
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+        self.show_comparison = open;
+    }
+
+    fn parsed_birthday(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(
+            self.birth_year.parse().ok()?,
+            self.birth_month.parse().ok()?,
+            self.birth_day.parse().ok()?,
+        )
+    }
+
+    /// 國家與預期壽命的設定欄位，設置頁與設置面板共用
+    fn show_expectancy_settings(&mut self, ui: &mut egui::Ui, birthday: Option<NaiveDate>) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("國家/地區:"));
+            egui::ComboBox::from_id_salt("country")
+                .selected_text(i18n::tr(life_table::country_name(&self.user_data.country)))
+                .show_ui(ui, |ui| {
+                    for (code, name) in life_table::countries() {
+                        ui.selectable_value(&mut self.user_data.country, code.to_string(), i18n::tr(name));
+                    }
+                });
+        });
+
+        let estimate = self.user_data.estimated_life_expectancy(birthday);
+        ui.label(tr!("模型估計壽命: {} 歲", format!("{:.1}", estimate)));
+        ui.small(tr!("依各國出生時平均餘命以 Gompertz 模型配適的估計，並非官方生命表"));
+
+        let mut manual = self.user_data.life_expectancy.is_some();
+        if ui.checkbox(&mut manual, tr!("手動設定預期壽命")).changed() {
+            self.user_data.life_expectancy = manual.then_some(estimate.round());
+        }
+        if let Some(life_expectancy) = &mut self.user_data.life_expectancy {
+            ui.add(egui::Slider::new(life_expectancy, 60.0..=120.0).suffix(tr!(" 歲")));
+        }
+    }
+
+    /// 讀取名言檔，失敗時顯示在錯誤橫幅
+    fn load_quotes(&mut self) {
+        match quotes::load(&self.quotes_path, self.profiles.language) {
+            Ok(quotes) => self.quotes = quotes,
+            Err(e) => self.storage_error = Some(e),
+        }
+    }
+
+    fn update_quote(&mut self) {
+        self.current_quote = match quotes::pick(&self.quotes, &self.profiles.quotes, Local::now(), self.launch_seed) {
+            Some(quote) => quote.text.clone(),
+            None => tr!("還沒有名言，請編輯 {}", self.quotes_path.display()),
+        };
+    }
+
+    /// 語言與名言設定
+    fn show_preferences(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("🌐 語言:"));
+            let mut language = self.profiles.language;
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(language.native_name())
+                .show_ui(ui, |ui| {
+                    for option in Language::ALL {
+                        ui.selectable_value(&mut language, option, option.native_name());
+                    }
+                });
+            if language != self.profiles.language {
+                self.profiles.language = language;
+                i18n::set_language(language);
+                self.save_user_data();
+            }
+        });
+
+        egui::CollapsingHeader::new(tr!("💭 名言")).show(ui, |ui| {
+            let before = self.profiles.quotes.clone();
+            let settings = &mut self.profiles.quotes;
+            ui.horizontal(|ui| {
+                ui.label(tr!("輪換:"));
+                egui::ComboBox::from_id_salt("quote_rotation")
+                    .selected_text(settings.rotation.label())
+                    .show_ui(ui, |ui| {
+                        for rotation in Rotation::ALL {
+                            ui.selectable_value(&mut settings.rotation, rotation, rotation.label());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr!("分類:"));
+                let category_name = |category: &str| if category.is_empty() { tr!("未分類").to_string() } else { category.to_string() };
+                egui::ComboBox::from_id_salt("quote_category")
+                    .selected_text(settings.category.as_deref().map_or(tr!("全部").to_string(), category_name))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut settings.category, None, tr!("全部"));
+                        for category in quotes::categories(&self.quotes) {
+                            ui.selectable_value(&mut settings.category, Some(category.to_string()), category_name(category));
+                        }
+                    });
+            });
+            ui.checkbox(&mut settings.favourites_only, tr!("只顯示收藏"));
+            ui.label(tr!("名言檔案: {}", self.quotes_path.display()));
+            if ui.button(tr!("🔄 重新載入名言")).clicked() {
+                self.load_quotes();
+            }
+            if self.profiles.quotes != before {
+                self.save_user_data();
+            }
+        });
+    }
+
     fn show_setup_page(&mut self, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.add_space(50.0);
             
-            ui.heading("🎂 人生倒數計時設置");
+            ui.heading(tr!("🎂 人生倒數計時設置"));
+            ui.add_space(10.0);
+            self.show_preferences(ui);
+            if self.profiles.profiles.len() > 1 {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    self.show_profile_switcher(ui);
+                    if ui.button(tr!("🗑 刪除此人資料")).clicked() {
+                        self.remove_active_profile();
+                    }
+                });
+            }
             ui.add_space(30.0);
             
             ui.horizontal(|ui| {
-                ui.label("姓名:");
+                ui.label(tr!("姓名:"));
                 ui.text_edit_singleline(&mut self.user_data.name);
             });
             ui.add_space(10.0);
             
-            ui.label("生日:");
+            ui.label(tr!("生日:"));
             ui.horizontal(|ui| {
-                ui.label("年:");
+                ui.label(tr!("年:"));
                 ui.text_edit_singleline(&mut self.birth_year);
-                ui.label("月:");
+                ui.label(tr!("月:"));
                 ui.text_edit_singleline(&mut self.birth_month);
-                ui.label("日:");
+                ui.label(tr!("日:"));
                 ui.text_edit_singleline(&mut self.birth_day);
             });
             ui.add_space(10.0);
             
             ui.horizontal(|ui| {
-                ui.label("性別:");
-                ui.selectable_value(&mut self.user_data.gender, Gender::Male, "男");
-                ui.selectable_value(&mut self.user_data.gender, Gender::Female, "女");
-                ui.selectable_value(&mut self.user_data.gender, Gender::Other, "其他");
+                ui.label(tr!("性別:"));
+                ui.selectable_value(&mut self.user_data.gender, Gender::Male, tr!("男"));
+                ui.selectable_value(&mut self.user_data.gender, Gender::Female, tr!("女"));
+                ui.selectable_value(&mut self.user_data.gender, Gender::Other, tr!("其他"));
             });
             ui.add_space(10.0);
             
-            ui.horizontal(|ui| {
-                ui.label("預期壽命:");
-                ui.add(egui::Slider::new(&mut self.user_data.life_expectancy, 60.0..=120.0)
-                    .suffix(" 歲"));
-            });
+            let birthday = self.parsed_birthday();
+            self.show_expectancy_settings(ui, birthday);
             ui.add_space(20.0);
             
-            if ui.button("✅ 完成設置").clicked() {
-                if let (Ok(year), Ok(month), Ok(day)) = (
-                    self.birth_year.parse::<i32>(),
-                    self.birth_month.parse::<u32>(),
-                    self.birth_day.parse::<u32>(),
-                ) {
-                    if let Some(birthday) = NaiveDate::from_ymd_opt(year, month, day) {
-                        self.user_data.birthday = Some(birthday);
-                        self.current_page = Page::Main;
-                        self.save_user_data();
+            if ui.button(tr!("✅ 完成設置")).clicked() {
+                if let Some(birthday) = birthday {
+                    self.user_data.birthday = Some(birthday);
+                    self.current_page = Page::Main;
+                    self.save_user_data();
+                }
+            }
+        });
+    }
+
+    /// 把編輯中的日記寫回並保存，切換日期或人資料前呼叫，避免未保存的內容被覆蓋
+    fn commit_journal_edit(&mut self) {
+        if journal::apply_edit(&mut self.user_data.journal, self.journal_date, &self.journal_text, self.journal_mood, &self.journal_tags) {
+            self.save_user_data();
+        }
+    }
+
+    /// 選取日記日期，並把該日內容載入編輯欄位
+    fn select_journal_date(&mut self, date: NaiveDate) {
+        self.journal_date = date;
+        self.journal_month = date.with_day(1).unwrap_or(date);
+        match self.user_data.journal.get(&date) {
+            Some(entry) => {
+                self.journal_text = entry.text.clone();
+                self.journal_mood = entry.mood;
+                self.journal_tags = entry.tags.join(", ");
+            }
+            None => {
+                self.journal_text.clear();
+                self.journal_mood = 3;
+                self.journal_tags.clear();
+            }
+        }
+    }
+
+    /// 日記頁：左側月曆與搜尋，右側編輯當天內容
+    fn show_journal_page(&mut self, ui: &mut egui::Ui) {
+        let today = Local::now().date_naive();
+        let mut selected = None;
+        let mut deleted = false;
+
+        ui.columns(2, |columns| {
+            let ui = &mut columns[0];
+            ui.horizontal(|ui| {
+                if ui.button("◀").clicked() {
+                    self.journal_month = self.journal_month - Months::new(1);
+                }
+                ui.strong(tr!("{} 年 {} 月", self.journal_month.year(), self.journal_month.month()));
+                if ui.button("▶").clicked() {
+                    self.journal_month = self.journal_month + Months::new(1);
+                }
+                if ui.button(tr!("今天")).clicked() {
+                    selected = Some(today);
+                }
+            });
+
+            egui::Grid::new("journal_calendar").spacing([4.0, 4.0]).show(ui, |ui| {
+                for weekday in [tr!("一"), tr!("二"), tr!("三"), tr!("四"), tr!("五"), tr!("六"), tr!("日")] {
+                    ui.label(weekday);
+                }
+                ui.end_row();
+
+                let first = self.journal_month;
+                for _ in 0..first.weekday().num_days_from_monday() {
+                    ui.label("");
+                }
+                let mut date = first;
+                while date.month() == first.month() {
+                    let mut text = egui::RichText::new(match self.user_data.journal.get(&date) {
+                        Some(entry) => format!("{}\n{}", date.day(), entry.mood_icon()),
+                        None => format!("{}\n ", date.day()),
+                    });
+                    if date == today {
+                        text = text.strong().color(egui::Color32::from_rgb(100, 149, 237));
                     }
+                    if ui.selectable_label(date == self.journal_date, text).clicked() {
+                        selected = Some(date);
+                    }
+                    if date.weekday() == Weekday::Sun {
+                        ui.end_row();
+                    }
+                    let Some(next) = date.succ_opt() else { break };
+                    date = next;
                 }
+            });
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label("🔍");
+                ui.text_edit_singleline(&mut self.journal_search);
+            });
+            egui::ScrollArea::vertical().id_salt("journal_search").max_height(200.0).show(ui, |ui| {
+                for (date, entry) in journal::search(&self.user_data.journal, &self.journal_search) {
+                    let first_line = entry.text.lines().next().unwrap_or("");
+                    if ui.selectable_label(date == self.journal_date, format!("{} {} {}", date, entry.mood_icon(), first_line)).clicked() {
+                        selected = Some(date);
+                    }
+                }
+            });
+
+            let ui = &mut columns[1];
+            ui.heading(format!("📔 {}", self.journal_date));
+            ui.horizontal(|ui| {
+                ui.label(tr!("心情:"));
+                for (mood, icon) in (1..).zip(journal::MOODS) {
+                    ui.selectable_value(&mut self.journal_mood, mood, icon);
+                }
+            });
+            ui.add(egui::TextEdit::multiline(&mut self.journal_text).desired_rows(12).desired_width(f32::INFINITY));
+            ui.horizontal(|ui| {
+                ui.label(tr!("標籤:"));
+                ui.add(egui::TextEdit::singleline(&mut self.journal_tags).hint_text(tr!("旅行, 家人")));
+            });
+            ui.horizontal(|ui| {
+                if ui.button(tr!("💾 保存")).clicked() {
+                    self.commit_journal_edit();
+                }
+                if self.user_data.journal.contains_key(&self.journal_date) && ui.button(tr!("🗑 刪除")).clicked() {
+                    self.user_data.journal.remove(&self.journal_date);
+                    self.save_user_data();
+                    deleted = true;
+                }
+            });
+        });
+
+        if deleted {
+            self.select_journal_date(self.journal_date);
+        } else if let Some(date) = selected {
+            // 切換日期時自動保存目前的內容
+            self.commit_journal_edit();
+            self.select_journal_date(date);
+        }
+    }
+
+    /// 主頁上的「往年今日」
+    fn show_on_this_day(&self, ui: &mut egui::Ui) {
+        let entries = journal::on_this_day(&self.user_data.journal, Local::now().date_naive());
+        if entries.is_empty() {
+            return;
+        }
+        ui.add_space(30.0);
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(tr!("📅 往年今日"));
+                ui.separator();
+                for (years, date, entry) in entries {
+                    ui.label(tr!("{} 年前（{}）{}", years, date, entry.mood_icon()));
+                    ui.label(egui::RichText::new(&entry.text).italics());
+                    if !entry.tags.is_empty() {
+                        ui.small(entry.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "));
+                    }
+                }
+            });
+        });
+    }
+
+    /// 人生週曆與 PNG 匯出
+    fn show_life_grid(&mut self, ui: &mut egui::Ui) {
+        let Some(birthday) = self.user_data.birthday else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        let grid = LifeGrid::new(birthday, today, self.user_data.effective_life_expectancy(), self.grid_unit, &self.user_data.goals);
+
+        egui::CollapsingHeader::new(tr!("🗓 人生週曆")).default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr!("每格:"));
+                ui.selectable_value(&mut self.grid_unit, GridUnit::Weeks, GridUnit::Weeks.label());
+                ui.selectable_value(&mut self.grid_unit, GridUnit::Months, GridUnit::Months.label());
+                if ui.button(tr!("🖼 匯出 PNG")).clicked() {
+                    let dir = dirs::picture_dir().or_else(dirs::home_dir).unwrap_or_default();
+                    let path = dir.join(format!("life_in_{}.png", if grid.unit == GridUnit::Weeks { "weeks" } else { "months" }));
+                    self.export_message = Some(match grid.export_png(&path) {
+                        Ok(()) => tr!("已匯出到 {}", path.display()),
+                        Err(e) => e,
+                    });
+                }
+            });
+            if let Some(message) = &self.export_message {
+                ui.label(message);
+            }
+
+            let width = ui.available_width();
+            let size = egui::vec2(width, width / grid.columns() as f32 * grid.rows as f32);
+            let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+            if let Some(text) = grid.paint(ui, rect) {
+                response.on_hover_text(text);
             }
         });
     }
-    // Fira Code Retina
+
+    /// 目標時間軸、倒數卡片與新增表單
+    fn show_goals(&mut self, ui: &mut egui::Ui, remaining_days: i64) {
+        let today = Local::now().date_naive();
+        let upcoming = goals::upcoming(&self.user_data.goals, today);
+        let span = remaining_days.max(1) as f32;
+        let mut changed = false;
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(tr!("🎯 目標與里程碑"));
+                ui.separator();
+
+                // 時間軸：左端為今天，右端為預期壽命終點
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 24.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                let axis_y = rect.center().y;
+                painter.line_segment(
+                    [egui::pos2(rect.left(), axis_y), egui::pos2(rect.right(), axis_y)],
+                    egui::Stroke::new(2.0, egui::Color32::GRAY),
+                );
+                for &(index, date) in &upcoming {
+                    let offset = (date - today).num_days();
+                    if !(0..=remaining_days).contains(&offset) {
+                        continue;
+                    }
+                    let center = egui::pos2(rect.left() + rect.width() * offset as f32 / span, axis_y);
+                    painter.circle_filled(center, 5.0, egui::Color32::from_rgb(100, 149, 237));
+                    let marker = egui::Rect::from_center_size(center, egui::vec2(12.0, 12.0));
+                    ui.interact(marker, ui.id().with(("goal_marker", index)), egui::Sense::hover())
+                        .on_hover_text(format!("{} ({})", self.user_data.goals[index].title, date));
+                }
+                ui.horizontal(|ui| {
+                    ui.label(tr!("今天"));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(tr!("剩餘 {} 天", remaining_days));
+                    });
+                });
+                ui.add_space(10.0);
+
+                for &(index, date) in &upcoming {
+                    let goal = &self.user_data.goals[index];
+                    let days = (date - today).num_days();
+                    let text = match days {
+                        0 => tr!("今天是「{}」", goal.title),
+                        d if d > 0 => tr!("距離「{}」還有 {} 天", goal.title, d),
+                        d => tr!("「{}」已逾期 {} 天", goal.title, -d),
+                    };
+                    let mut detail = format!("{} · {}", date, goal.recurrence.label());
+                    if (0..=remaining_days).contains(&days) {
+                        detail += &tr!(" · 位於剩餘時間的 {}%", format!("{:.1}", days as f32 / span * 100.0));
+                    }
+
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(egui::RichText::new(text).size(16.0).strong());
+                                ui.label(detail);
+                            });
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("🗑").on_hover_text(tr!("刪除")).clicked() {
+                                    self.user_data.goals.remove(index);
+                                    changed = true;
+                                } else if ui.button(tr!("✅ 完成")).clicked() {
+                                    self.user_data.goals[index].complete(today);
+                                    changed = true;
+                                }
+                            });
+                        });
+                    });
+                    // 索引在刪除後失效，下一幀再繼續顯示
+                    if changed {
+                        break;
+                    }
+                }
+
+                let done: Vec<usize> = (0..self.user_data.goals.len())
+                    .filter(|&i| self.user_data.goals[i].is_done())
+                    .collect();
+                if !changed && !done.is_empty() {
+                    egui::CollapsingHeader::new(tr!("已完成 ({})", done.len())).show(ui, |ui| {
+                        for index in done {
+                            ui.horizontal(|ui| {
+                                let goal = &self.user_data.goals[index];
+                                ui.label(format!("✔ {} ({})", goal.title, goal.date));
+                                if ui.small_button("🗑").clicked() {
+                                    self.user_data.goals.remove(index);
+                                    changed = true;
+                                }
+                            });
+                            if changed {
+                                break;
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr!("名稱:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.goal_title).desired_width(120.0));
+                    ui.label(tr!("日期:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.goal_date).hint_text("2030-01-01").desired_width(90.0));
+                    egui::ComboBox::from_id_salt("goal_recurrence")
+                        .selected_text(self.goal_recurrence.label())
+                        .show_ui(ui, |ui| {
+                            for recurrence in Recurrence::ALL {
+                                ui.selectable_value(&mut self.goal_recurrence, recurrence, recurrence.label());
+                            }
+                        });
+                    let date = NaiveDate::parse_from_str(self.goal_date.trim(), "%Y-%m-%d").ok();
+                    let title = self.goal_title.trim().to_string();
+                    let button = ui.add_enabled(date.is_some() && !title.is_empty(), egui::Button::new(tr!("➕ 新增")));
+                    if let (true, Some(date)) = (button.clicked(), date) {
+                        self.user_data.goals.push(Goal::new(title, date, self.goal_recurrence));
+                        self.goal_title.clear();
+                        self.goal_date.clear();
+                        changed = true;
+                    }
+                });
+            });
+        });
+
+        if changed {
+            self.save_user_data();
+        }
+    }
 
     fn show_main_page(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if let Some(remaining_days) = self.calculate_remaining_days() {
-                    let name = if self.user_data.name.is_empty() { "朋友" } else { &self.user_data.name };
-                    ui.label(format!("👋 {}，還剩 {} 天", name, remaining_days));
+                if let Some(remaining_days) = self.user_data.calculate_remaining_days() {
+                    ui.label(tr!("👋 {}，還剩 {} 天", self.user_data.display_name(), remaining_days));
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("⚙️設置").clicked() {
+                        if ui.button(tr!("⚙️設置")).clicked() {
                             self.show_settings = !self.show_settings;
                         }
+                        self.show_profile_switcher(ui);
+                        let journal_open = self.current_page == Page::Journal;
+                        if ui.selectable_label(journal_open, tr!("📔 日記")).clicked() {
+                            self.current_page = if journal_open { Page::Main } else { Page::Journal };
+                        }
                     });
                 }
             });
@@ -367,119 +980,181 @@ impl LifeCountdownApp {
 
         if self.show_settings {
             egui::SidePanel::right("settings_panel").show(ctx, |ui| {
-                ui.heading("設置");
+                ui.heading(tr!("設置"));
                 ui.separator();
+
+                self.show_preferences(ui);
+                ui.add_space(10.0);
                 
                 ui.horizontal(|ui| {
-                    ui.label("姓名:");
+                    ui.label(tr!("姓名:"));
                     ui.text_edit_singleline(&mut self.user_data.name);
                 });
                 ui.add_space(10.0);
                 
-                ui.label("預期壽命:");
-                ui.add(egui::Slider::new(&mut self.user_data.life_expectancy, 60.0..=120.0)
-                    .suffix(" 歲"));
+                ui.horizontal(|ui| {
+                    ui.label(tr!("性別:"));
+                    ui.selectable_value(&mut self.user_data.gender, Gender::Male, tr!("男"));
+                    ui.selectable_value(&mut self.user_data.gender, Gender::Female, tr!("女"));
+                    ui.selectable_value(&mut self.user_data.gender, Gender::Other, tr!("其他"));
+                });
+                ui.add_space(10.0);
+
+                self.show_expectancy_settings(ui, self.user_data.birthday);
                 
                 ui.add_space(10.0);
                 
-                if ui.button("重新設定生日").clicked() {
+                if ui.button(tr!("重新設定生日")).clicked() {
                     self.current_page = Page::Setup;
                     self.show_settings = false;
                 }
                 
                 ui.add_space(10.0);
                 
-                if ui.button("保存設置").clicked() {
+                if ui.button(tr!("保存設置")).clicked() {
                     self.save_user_data();
                     self.show_settings = false;
                 }
+
+                if self.profiles.profiles.len() > 1 {
+                    ui.add_space(10.0);
+                    if ui.button(tr!("🗑 刪除此人資料")).clicked() {
+                        self.remove_active_profile();
+                    }
+                }
+
+                ui.add_space(10.0);
+                self.show_transfer_settings(ui);
             });
         }
 
+        if self.show_comparison {
+            self.show_comparison_window(ctx);
+        }
+
+        if self.current_page == Page::Journal {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                self.show_journal_page(ui);
+            });
+            return;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(20.0);
-                
-                if let Some(remaining_days) = self.calculate_remaining_days() {
-                    ui.add_space(30.0);
-                    ui.label(
-                        egui::RichText::new(format!("{}", remaining_days))
-                            .size(80.0)
-                            .color(egui::Color32::from_rgb(100, 149, 237))
-                            .strong()
-                    );
-                    
-                    ui.label(
-                        egui::RichText::new("天")
-                            .size(40.0)
-                            .color(egui::Color32::from_rgb(100, 149, 237))
-                    );
-                    
-                    ui.add_space(10.0);
-                    ui.label("剩餘時間");
-                    ui.add_space(30.0);
-                    
-                    if let Some(progress) = self.calculate_life_progress() {
-                        ui.label(format!("人生進度: {:.1}%", progress));
-                        let progress_bar = egui::ProgressBar::new(progress / 100.0)
-                            .text(format!("{:.1}%", progress));
-                        ui.add(progress_bar);
-                    }
-                    
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.vertical_centered(|ui| {
                     ui.add_space(20.0);
                     
-                    ui.group(|ui| {
-                        ui.vertical(|ui| {
-                            ui.label("📊 統計訊息");
-                            ui.separator();
-                            
-                            if let Some(age) = self.get_age_in_years() {
-                                ui.label(format!("當前年齡: {:.1} 歲", age));
-                            }
-                            
-                            if let Some(days_lived) = self.calculate_days_lived() {
-                                ui.label(format!("已度過: {} 天", days_lived));
-                            }
-                            
-                            ui.label(format!("預期壽命: {:.0} 歲", self.user_data.life_expectancy));
-                            
-                            let years = remaining_days / 365;
-                            let months = (remaining_days % 365) / 30;
-                            let days = remaining_days % 30;
-                            
-                            ui.label(format!("約為: {} 年 {} 月 {} 天", years, months, days));
-                        });
-                    });
-                    
-                    ui.add_space(30.0);
-                    
-                    ui.group(|ui| {
-                        ui.vertical_centered(|ui| {
-                            ui.label("💭 今日思考");
-                            ui.separator();
+                    if let Some(remaining_days) = self.user_data.calculate_remaining_days() {
+                        ui.add_space(30.0);
+                        ui.label(
+                            egui::RichText::new(format!("{}", remaining_days))
+                                .size(80.0)
+                                .color(egui::Color32::from_rgb(100, 149, 237))
+                                .strong()
+                        );
+                        
+                        ui.label(
+                            egui::RichText::new(tr!("天"))
+                                .size(40.0)
+                                .color(egui::Color32::from_rgb(100, 149, 237))
+                        );
+                        
+                        ui.add_space(10.0);
+                        ui.label(tr!("剩餘時間"));
+                        let remaining = self.user_data.remaining_time(Local::now().naive_local()).unwrap_or_default();
+                        if self.profiles.ticker {
+                            ui.add_space(10.0);
                             ui.label(
-                                egui::RichText::new(&self.current_quote)
-                                    .size(16.0)
-                                    .color(egui::Color32::from_rgb(105, 105, 105))
-                                    .italics()
+                                egui::RichText::new(tr!("{} 年 {} 月 {} 天 {}", remaining.years, remaining.months, remaining.days, remaining.clock()))
+                                    .size(28.0)
+                                    .monospace()
+                                    .color(egui::Color32::from_rgb(100, 149, 237))
                             );
-                        });
-                    });
-                    
-                    if remaining_days <= 365 {
-                        ui.add_space(20.0);
-                        ui.colored_label(
-                            egui::Color32::from_rgb(255, 69, 0),
-                            "⚠️ 珍惜時光"
-                        );
-                    } else if remaining_days <= 1825 {
+                        }
+                        if ui.toggle_value(&mut self.profiles.ticker, tr!("⏱ 即時秒數")).changed() {
+                            self.save_user_data();
+                        }
+                        ui.add_space(30.0);
+                        
+                        if let Some(progress) = self.user_data.calculate_life_progress() {
+                            ui.label(tr!("人生進度: {}%", format!("{:.1}", progress)));
+                            let progress_bar = egui::ProgressBar::new(progress / 100.0)
+                                .text(format!("{:.1}%", progress));
+                            ui.add(progress_bar);
+                        }
+                        
                         ui.add_space(20.0);
-                        ui.colored_label(
-                            egui::Color32::from_rgb(255, 140, 0),
-                            "⏰ 時間不多"
-                        );
+                        
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(tr!("📊 統計訊息"));
+                                ui.separator();
+                                
+                                if let Some(age) = self.user_data.get_age_in_years() {
+                                    ui.label(tr!("當前年齡: {} 歲", format!("{:.1}", age)));
+                                }
+                                
+                                if let Some(days_lived) = self.user_data.calculate_days_lived() {
+                                    ui.label(tr!("已度過: {} 天", days_lived));
+                                }
+                                
+                                ui.label(tr!("預期壽命: {} 歲", format!("{:.1}", self.user_data.effective_life_expectancy())));
+                                
+                                ui.label(tr!("剩餘: {} 年 {} 月 {} 天 {} 小時", remaining.years, remaining.months, remaining.days, remaining.hours));
+                            });
+                        });
+                        
+                        ui.add_space(30.0);
+                        self.show_life_grid(ui);
+
+                        ui.add_space(30.0);
+                        self.show_goals(ui, remaining_days);
+                        self.show_on_this_day(ui);
+                        
+                        ui.add_space(30.0);
+                        
+                        ui.group(|ui| {
+                            ui.vertical_centered(|ui| {
+                                ui.label(tr!("💭 今日思考"));
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label(
+                                        egui::RichText::new(&self.current_quote)
+                                            .size(16.0)
+                                            .color(egui::Color32::from_rgb(105, 105, 105))
+                                            .italics()
+                                    );
+                                    let favourites = &mut self.profiles.quotes.favourites;
+                                    let position = favourites.iter().position(|quote| *quote == self.current_quote);
+                                    let (icon, hint) = if position.is_some() { ("★", tr!("取消收藏")) } else { ("☆", tr!("收藏")) };
+                                    if ui.small_button(icon).on_hover_text(hint).clicked() {
+                                        match position {
+                                            Some(index) => {
+                                                favourites.remove(index);
+                                            }
+                                            None => favourites.push(self.current_quote.clone()),
+                                        }
+                                        self.save_user_data();
+                                    }
+                                });
+                            });
+                        });
+                        
+                        if remaining_days <= 365 {
+                            ui.add_space(20.0);
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 69, 0),
+                                tr!("⚠️ 珍惜時光")
+                            );
+                        } else if remaining_days <= 1825 {
+                            ui.add_space(20.0);
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 140, 0),
+                                tr!("⏰ 時間不多")
+                            );
+                        }
                     }
-                }
+                });
             });
         });
     }
@@ -515,6 +1190,17 @@ impl eframe::App for LifeCountdownApp {
             ..egui::Visuals::dark()
         });
 
+        // 每隔一段時間檢查設定檔是否被其他程式修改，不必每一幀都讀檔案資訊
+        const DISK_POLL: Duration = Duration::from_secs(2);
+        if self.disk_checked.is_none_or(|checked| checked.elapsed() >= DISK_POLL) {
+            self.disk_checked = Some(Instant::now());
+            if self.merge_external_changes() && self.storage_error.is_none() {
+                self.storage_error = Some(tr!("設定檔已在其他地方更新，已與這裡的變更合併").to_string());
+            }
+        }
+        ctx.request_repaint_after(DISK_POLL);
+        self.show_storage_error(ctx);
+
         // 渲染頁面
         match self.current_page {
             Page::Setup => {
@@ -522,16 +1208,23 @@ impl eframe::App for LifeCountdownApp {
                     self.show_setup_page(ui);
                 });
             }
-            Page::Main => {
+            Page::Main | Page::Journal => {
                 self.show_main_page(ctx);
             }
         }
         
-        // 每分鐘刷新一次
-        ctx.request_repaint_after(std::time::Duration::from_secs(60));
+        // 每分鐘刷新一次，開啟即時秒數時每秒刷新
+        let interval = if self.profiles.ticker && self.current_page == Page::Main { 1 } else { 60 };
+        ctx.request_repaint_after(std::time::Duration::from_secs(interval));
     }
 }
 fn main() -> Result<(), eframe::Error> {
+    // 有參數時以命令列模式執行，不開視窗
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     // 1) 编译时加载 playstore.png
     let png_bytes = include_bytes!("../assets/playstore.png");
     let img = load_from_memory(png_bytes)
@@ -559,4 +1252,84 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| Ok(Box::new(LifeCountdownApp::new(cc)))),
     )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::JournalEntry;
+
+    fn config_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("life_countdown_app_{}_{}", name, std::process::id())).join("config.json")
+    }
+
+    /// 使用暫存目錄中設定檔的應用程式，已載入 `profiles`
+    fn app_with(name: &str, profiles: Profiles) -> LifeCountdownApp {
+        let path = config_path(name);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        let mut storage = Storage::new(path);
+        storage.save(&profiles).unwrap();
+        let mut app = LifeCountdownApp { storage, ..Default::default() };
+        app.load_user_data();
+        app
+    }
+
+    /// 另一個程式（例如命令列）把 `edit` 套用到設定檔
+    fn edit_elsewhere(name: &str, edit: impl FnOnce(&mut Profiles)) {
+        let mut cli = Storage::new(config_path(name));
+        let mut profiles = cli.load().unwrap().profiles.unwrap();
+        edit(&mut profiles);
+        cli.save(&profiles).unwrap();
+    }
+
+    #[test]
+    fn profile_changes_survive_external_edits() {
+        let mut app = app_with("external", Profiles::new(vec![UserData { name: "甲".into(), ..Default::default() }]));
+
+        edit_elsewhere("external", |profiles| profiles.profiles[0].country = "日本".into());
+        app.add_profile();
+        assert_eq!(app.profiles.profiles.len(), 2);
+        assert_eq!(app.profiles.active, 1);
+        assert_eq!(app.profiles.profiles[0].country, "日本");
+
+        edit_elsewhere("external", |profiles| profiles.ticker = true);
+        app.remove_active_profile();
+        let saved = app.storage.load().unwrap().profiles.unwrap();
+        assert_eq!(saved.profiles.len(), 1);
+        assert_eq!(saved.active().name, "甲");
+        assert!(saved.ticker);
+
+        // 輸入到一半的名字不會被其他程式的修改蓋掉
+        app.user_data.name = "甲乙".into();
+        edit_elsewhere("external", |profiles| profiles.profiles.push(UserData::default()));
+        assert!(app.merge_external_changes());
+        assert_eq!(app.user_data.name, "甲乙");
+        assert_eq!(app.profiles.profiles.len(), 2);
+    }
+
+    #[test]
+    fn removing_a_profile_discards_its_journal_draft() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let entry = JournalEntry { text: "乙的日記".into(), mood: 4, tags: vec![] };
+        let next = UserData { name: "乙".into(), journal: [(date, entry)].into(), ..Default::default() };
+        let mut app = app_with("remove", Profiles::new(vec![UserData { name: "甲".into(), ..Default::default() }, next]));
+
+        app.select_journal_date(date);
+        app.journal_text = "甲還沒保存的草稿".into();
+        app.remove_active_profile();
+        assert_eq!(app.user_data.name, "乙");
+        assert_eq!(app.user_data.journal[&date].text, "乙的日記");
+
+        // 空白的草稿也不能把下一份人資料當天的日記刪掉
+        let mut app = app_with("remove_empty", app.profiles.clone());
+        app.profiles.profiles.insert(0, UserData::default());
+        app.switch_profile(0);
+        app.select_journal_date(date);
+        app.journal_text.clear();
+        app.remove_active_profile();
+        assert_eq!(app.user_data.journal[&date].text, "乙的日記");
+
+        let saved = app.storage.load().unwrap().profiles.unwrap();
+        assert_eq!(saved.profiles.len(), 1);
+        assert_eq!(saved.active().journal[&date].text, "乙的日記");
+    }
+}
@@ -0,0 +1,248 @@
+//! 使用者資料與多人資料設定檔
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::countdown::{self, Breakdown};
+use crate::goals::{self, Goal};
+use crate::i18n::Language;
+use crate::journal::Journal;
+use crate::life_table;
+use crate::quotes::QuoteSettings;
+
+/// 人資料的識別碼，建立時產生，匯入時用來對應同一份資料；舊版設定檔沒有時在讀取時補上
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProfileId(pub String);
+
+impl Default for ProfileId {
+    fn default() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        ProfileId(format!("{:016x}-{:x}-{:x}", nanos, std::process::id(), count))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserData {
+    #[serde(default)]
+    pub id: ProfileId,
+    pub birthday: Option<NaiveDate>,
+    /// 手動設定的預期壽命；為 None 時依生命表估算
+    #[serde(default)]
+    pub life_expectancy: Option<f32>,
+    pub name: String,
+    pub gender: Gender,
+    pub country: String,
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+    #[serde(default)]
+    pub journal: Journal,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Gender {
+    #[default]
+    Male,
+    Female,
+    Other,
+}
+
+impl UserData {
+    /// 顯示用名稱，未填寫時為「朋友」
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() { tr!("朋友") } else { &self.name }
+    }
+
+    /// 依生命表估算的預期壽命，以 `birthday` 算出目前年齡後取條件平均餘命
+    pub fn estimated_life_expectancy(&self, birthday: Option<NaiveDate>) -> f32 {
+        let age = birthday
+            .map(|birthday| countdown::years_between(birthday, Local::now().date_naive()))
+            .unwrap_or(0.0);
+        life_table::expected_lifespan(&self.country, &self.gender, age)
+    }
+
+    /// 實際使用的預期壽命：手動設定優先，否則依生命表估算
+    pub fn effective_life_expectancy(&self) -> f32 {
+        self.life_expectancy
+            .unwrap_or_else(|| self.estimated_life_expectancy(self.birthday))
+    }
+
+    pub fn calculate_days_lived(&self) -> Option<i64> {
+        if let Some(birthday) = self.birthday {
+            let now = Local::now().date_naive();
+            let days_lived = (now - birthday).num_days();
+            Some(days_lived)
+        } else {
+            None
+        }
+    }
+
+    pub fn expected_death_date(&self) -> Option<NaiveDate> {
+        let birthday = self.birthday?;
+        Some(countdown::add_years(birthday, self.effective_life_expectancy()))
+    }
+
+    /// 從 `now` 到預期壽命終點當天零時的剩餘時間
+    pub fn remaining_time(&self, now: NaiveDateTime) -> Option<Breakdown> {
+        let end = self.expected_death_date()?.and_hms_opt(0, 0, 0)?;
+        Some(Breakdown::between(now, end))
+    }
+
+    pub fn calculate_remaining_days(&self) -> Option<i64> {
+        if let Some(expected_death_date) = self.expected_death_date() {
+            let now = Local::now().date_naive();
+            let remaining_days = (expected_death_date - now).num_days();
+            Some(remaining_days.max(0))
+        } else {
+            None
+        }
+    }
+
+    pub fn calculate_life_progress(&self) -> Option<f32> {
+        if let Some(birthday) = self.birthday {
+            let now = Local::now().date_naive();
+            let days_lived = (now - birthday).num_days() as f32;
+            let total_expected_days = (self.expected_death_date()? - birthday).num_days().max(1) as f32;
+            Some((days_lived / total_expected_days * 100.0).min(100.0))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_age_in_years(&self) -> Option<f32> {
+        if let Some(birthday) = self.birthday {
+            let now = Local::now().date_naive();
+            Some(countdown::years_between(birthday, now))
+        } else {
+            None
+        }
+    }
+
+    /// 最近一個尚未完成的目標
+    pub fn next_goal(&self, today: NaiveDate) -> Option<(&Goal, NaiveDate)> {
+        goals::upcoming(&self.goals, today)
+            .into_iter()
+            .find(|&(_, date)| date >= today)
+            .map(|(index, date)| (&self.goals[index], date))
+    }
+}
+
+/// 設定檔內容：所有人資料、目前選取的那一份，以及不分人資料的偏好設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profiles {
+    pub profiles: Vec<UserData>,
+    pub active: usize,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub quotes: QuoteSettings,
+    /// 主頁面以秒為單位即時顯示剩餘時間
+    #[serde(default)]
+    pub ticker: bool,
+}
+
+impl Default for Profiles {
+    /// 總是至少有一份人資料
+    fn default() -> Self {
+        Profiles::new(vec![UserData::default()])
+    }
+}
+
+impl Profiles {
+    pub fn new(profiles: Vec<UserData>) -> Self {
+        Profiles { profiles, active: 0, language: Language::default(), quotes: QuoteSettings::default(), ticker: false }
+    }
+
+    pub fn active(&self) -> &UserData {
+        &self.profiles[self.active]
+    }
+
+    pub fn position(&self, id: &ProfileId) -> Option<usize> {
+        self.profiles.iter().position(|profile| &profile.id == id)
+    }
+
+    /// 把這裡從 `base` 之後的變更套到其他程式寫入的 `theirs` 上，以 `id` 對應人資料。
+    /// 只有一邊改過的人資料與設定取改過的那邊，兩邊都改過時保留這裡的版本；
+    /// 第二個傳回值表示是否有這種衝突
+    pub fn merge(&self, base: &Profiles, theirs: Profiles) -> (Profiles, bool) {
+        let mut conflict = false;
+        let mut merged: Vec<UserData> = theirs
+            .profiles
+            .into_iter()
+            .filter_map(|their| {
+                let Some(original) = base.position(&their.id).map(|index| &base.profiles[index]) else {
+                    return Some(their);
+                };
+                match self.position(&their.id).map(|index| &self.profiles[index]) {
+                    // 這裡刪除的人資料
+                    None => None,
+                    Some(ours) if ours == original => Some(their),
+                    Some(ours) => {
+                        conflict |= their != *original;
+                        Some(ours.clone())
+                    }
+                }
+            })
+            .collect();
+        for ours in &self.profiles {
+            if base.position(&ours.id).is_none() && !merged.iter().any(|profile| profile.id == ours.id) {
+                merged.push(ours.clone());
+            }
+        }
+        if merged.is_empty() {
+            merged.push(UserData::default());
+        }
+
+        fn pick<T: PartialEq + Clone>(ours: &T, base: &T, theirs: T) -> T {
+            if ours != base { ours.clone() } else { theirs }
+        }
+        let mut profiles = Profiles::new(merged);
+        profiles.active = profiles.position(&self.active().id).unwrap_or(self.active.min(profiles.profiles.len() - 1));
+        profiles.language = pick(&self.language, &base.language, theirs.language);
+        profiles.quotes = pick(&self.quotes, &base.quotes, theirs.quotes);
+        profiles.ticker = pick(&self.ticker, &base.ticker, theirs.ticker);
+        (profiles, conflict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> UserData {
+        UserData { name: name.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn merge_keeps_changes_from_both_sides() {
+        let base = Profiles::new(vec![named("甲"), named("乙"), named("丙")]);
+        let mut ours = base.clone();
+        ours.profiles[0].country = "台灣".into();
+        ours.profiles.remove(2);
+        ours.profiles.push(named("丁"));
+        ours.active = 2;
+        let mut theirs = base.clone();
+        theirs.profiles[1].country = "日本".into();
+        theirs.profiles.push(named("戊"));
+        theirs.ticker = true;
+
+        let (merged, conflict) = ours.merge(&base, theirs.clone());
+        assert!(!conflict);
+        let names: Vec<_> = merged.profiles.iter().map(|profile| profile.name.as_str()).collect();
+        assert_eq!(names, ["甲", "乙", "戊", "丁"]);
+        assert_eq!(merged.profiles[0].country, "台灣");
+        assert_eq!(merged.profiles[1].country, "日本");
+        assert_eq!(merged.active().name, "丁");
+        assert!(merged.ticker);
+
+        // 兩邊都改了同一份時保留這裡的版本
+        theirs.profiles[0].country = "韓國".into();
+        let (merged, conflict) = ours.merge(&base, theirs);
+        assert!(conflict);
+        assert_eq!(merged.profiles[0].country, "台灣");
+    }
+}
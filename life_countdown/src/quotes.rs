@@ -0,0 +1,212 @@
+//! 名言：從使用者可編輯的文字檔讀取，依分類、收藏與輪換方式挑選
+//!
+//! 檔案格式為每行一句，`[分類]` 開始新的分類，`#` 開頭的行為註解。
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::i18n::Language;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    Hourly,
+    Daily,
+    RandomPerLaunch,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 3] = [Rotation::Hourly, Rotation::Daily, Rotation::RandomPerLaunch];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rotation::Hourly => tr!("每小時"),
+            Rotation::Daily => tr!("每天"),
+            Rotation::RandomPerLaunch => tr!("每次啟動隨機"),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteSettings {
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// 只從這個分類挑選，None 為全部
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub favourites_only: bool,
+    /// 收藏的名言原文
+    #[serde(default)]
+    pub favourites: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub text: String,
+    pub category: String,
+}
+
+pub fn parse(text: &str) -> Vec<Quote> {
+    let mut category = String::new();
+    let mut quotes = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            category = name.trim().to_string();
+        } else {
+            quotes.push(Quote { text: line.to_string(), category: category.clone() });
+        }
+    }
+    quotes
+}
+
+/// 依語言建立的預設名言檔
+pub fn default_file(language: Language) -> &'static str {
+    match language {
+        Language::TraditionalChinese => "\
+# 每行一句名言；以 [分類] 開始新的分類，# 開頭為註解
+[時間]
+時間是最寶貴的資源
+時間不會回頭，所以要向前看
+每一刻都是禮物，這就是為什麼叫現在
+[人生]
+每一天都是新的開始
+珍惜當下，活在此刻
+生命的意義在於創造價值
+今天是你餘生的第一天
+[行動]
+不要等待機會，要創造機會
+",
+        Language::SimplifiedChinese => "\
+# 每行一句名言；以 [分类] 开始新的分类，# 开头为注释
+[时间]
+时间是最宝贵的资源
+时间不会回头，所以要向前看
+每一刻都是礼物，这就是为什么叫现在
+[人生]
+每一天都是新的开始
+珍惜当下，活在此刻
+生命的意义在于创造价值
+今天是你余生的第一天
+[行动]
+不要等待机会，要创造机会
+",
+        Language::English => "\
+# One quote per line; [Category] starts a new category, lines starting with # are comments
+[Time]
+Time is the most precious resource
+Time never turns back, so look forward
+Every moment is a gift, that is why it is called the present
+[Life]
+Every day is a new beginning
+Cherish the present and live in the moment
+The meaning of life lies in creating value
+Today is the first day of the rest of your life
+[Action]
+Do not wait for opportunities, create them
+",
+    }
+}
+
+/// 讀取名言檔；檔案不存在時先寫入預設內容
+pub fn load(path: &Path, language: Language) -> Result<Vec<Quote>, String> {
+    if !path.exists() {
+        let text = default_file(language);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| tr!("無法建立名言檔案 {}: {}", path.display(), e))?;
+        }
+        fs::write(path, text).map_err(|e| tr!("無法建立名言檔案 {}: {}", path.display(), e))?;
+        return Ok(parse(text));
+    }
+    let text = fs::read_to_string(path).map_err(|e| tr!("無法讀取名言檔案 {}: {}", path.display(), e))?;
+    Ok(parse(&text))
+}
+
+/// 檔案中出現過的分類，依出現順序
+pub fn categories(quotes: &[Quote]) -> Vec<&str> {
+    let mut categories: Vec<&str> = Vec::new();
+    for quote in quotes {
+        if !categories.contains(&quote.category.as_str()) {
+            categories.push(&quote.category);
+        }
+    }
+    categories
+}
+
+/// 依設定挑出目前要顯示的名言；篩選後沒有任何名言時改從全部挑選
+pub fn pick<'a>(quotes: &'a [Quote], settings: &QuoteSettings, now: DateTime<Local>, launch_seed: u64) -> Option<&'a Quote> {
+    let pool: Vec<&Quote> = quotes
+        .iter()
+        .filter(|quote| settings.category.as_ref().is_none_or(|category| &quote.category == category))
+        .filter(|quote| !settings.favourites_only || settings.favourites.contains(&quote.text))
+        .collect();
+    let pool = if pool.is_empty() { quotes.iter().collect() } else { pool };
+    if pool.is_empty() {
+        return None;
+    }
+    let days = now.date_naive().num_days_from_ce() as u64;
+    let slot = match settings.rotation {
+        Rotation::Hourly => days * 24 + now.hour() as u64,
+        Rotation::Daily => days,
+        Rotation::RandomPerLaunch => launch_seed,
+    };
+    Some(pool[(slot % pool.len() as u64) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_categories_and_comments() {
+        let quotes = parse("# 註解\n無分類\n[甲]\n一\n\n[ 乙 ]\n二\n三\n");
+        assert_eq!(quotes.len(), 4);
+        assert_eq!(quotes[0].category, "");
+        assert_eq!(quotes[3], Quote { text: "三".into(), category: "乙".into() });
+        assert_eq!(categories(&quotes), ["", "甲", "乙"]);
+        for language in Language::ALL {
+            assert_eq!(parse(default_file(language)).len(), 8);
+        }
+    }
+
+    #[test]
+    fn rotation_changes_with_the_period() {
+        let quotes = parse(default_file(Language::TraditionalChinese));
+        let hourly = QuoteSettings::default();
+        assert_ne!(pick(&quotes, &hourly, at(1, 9), 0), pick(&quotes, &hourly, at(1, 10), 0));
+
+        let daily = QuoteSettings { rotation: Rotation::Daily, ..Default::default() };
+        assert_eq!(pick(&quotes, &daily, at(1, 9), 0), pick(&quotes, &daily, at(1, 23), 0));
+        assert_ne!(pick(&quotes, &daily, at(1, 9), 0), pick(&quotes, &daily, at(2, 9), 0));
+
+        let random = QuoteSettings { rotation: Rotation::RandomPerLaunch, ..Default::default() };
+        assert_eq!(pick(&quotes, &random, at(1, 9), 3), pick(&quotes, &random, at(5, 1), 3));
+    }
+
+    #[test]
+    fn filters_by_category_and_favourites() {
+        let quotes = parse(default_file(Language::TraditionalChinese));
+        let settings = QuoteSettings { category: Some("行動".into()), ..Default::default() };
+        assert_eq!(pick(&quotes, &settings, at(1, 0), 0).unwrap().text, "不要等待機會，要創造機會");
+
+        let favourite = "珍惜當下，活在此刻".to_string();
+        let settings = QuoteSettings { favourites_only: true, favourites: vec![favourite.clone()], ..Default::default() };
+        for hour in 0..24 {
+            assert_eq!(pick(&quotes, &settings, at(1, hour), 0).unwrap().text, favourite);
+        }
+
+        let empty = QuoteSettings { category: Some("不存在".into()), ..Default::default() };
+        assert!(pick(&quotes, &empty, at(1, 0), 0).is_some());
+        assert!(pick(&[], &empty, at(1, 0), 0).is_none());
+    }
+}
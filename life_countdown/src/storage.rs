@@ -0,0 +1,375 @@
+//! 設定檔的讀寫：帶版本號的格式與逐版轉換、先寫暫存檔再改名，並保留最近幾次的備份
+//!
+//! 版本紀錄：
+//! - 0：只有一份 `UserData`
+//! - 1：`{ profiles, active }`，沒有版本欄位
+//! - 2：加上 `version` 欄位
+//! - 3：每份人資料加上 `id`，讀取舊檔時自動產生並立即寫回
+
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::profile::Profiles;
+
+pub const CURRENT_VERSION: u64 = 3;
+
+/// 保留的備份數
+pub const BACKUPS: usize = 5;
+
+/// 系統設定目錄下的資料夾，圖形介面與命令列共用
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("life_countdown"))
+}
+
+#[derive(Serialize)]
+struct Stored<'a> {
+    version: u64,
+    #[serde(flatten)]
+    profiles: &'a Profiles,
+}
+
+/// 未標版本的檔案依內容判斷是第 0 還是第 1 版
+fn version_of(value: &Value) -> u64 {
+    match value.get("version") {
+        Some(version) => version.as_u64().unwrap_or(u64::MAX),
+        None if value.get("profiles").is_some() => 1,
+        None => 0,
+    }
+}
+
+/// 逐版轉換到目前的格式
+pub fn migrate(mut value: Value) -> Result<Value, String> {
+    loop {
+        match version_of(&value) {
+            0 => value = json!({ "profiles": [value], "active": 0 }),
+            1 => value["version"] = json!(2),
+            // `id` 由反序列化時的預設值產生
+            2 => value["version"] = json!(3),
+            CURRENT_VERSION => return Ok(value),
+            version => return Err(tr!("設定檔版本 {} 無法辨識，可能由較新的版本寫入", version)),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Profiles, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| tr!("設定檔格式錯誤: {}", e))?;
+    let mut profiles: Profiles = serde_json::from_value(migrate(value)?).map_err(|e| tr!("設定檔內容錯誤: {}", e))?;
+    if profiles.profiles.is_empty() {
+        profiles = Profiles::default();
+    }
+    profiles.active = profiles.active.min(profiles.profiles.len() - 1);
+    Ok(profiles)
+}
+
+pub fn to_json(profiles: &Profiles) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Stored { version: CURRENT_VERSION, profiles })
+}
+
+#[derive(Default)]
+pub struct Storage {
+    path: PathBuf,
+    /// 最後一次讀到或寫入的內容，沒有變更時不重寫
+    last_saved: Option<String>,
+    /// 這次執行是否已經輪替過備份；每次執行最多輪替一次
+    rotated: bool,
+    /// 設定檔由較新的版本寫入或無法移開時停用保存，以免覆蓋
+    read_only: Option<String>,
+    /// 最後一次讀寫後檔案的修改時間與大小，用來察覺其他程式（例如命令列）的修改
+    stamp: Option<(SystemTime, u64)>,
+}
+
+/// 讀取的結果；主檔損毀但從備份救回時附上警告
+pub struct Loaded {
+    pub profiles: Option<Profiles>,
+    pub warning: Option<String>,
+}
+
+impl Storage {
+    pub fn new(path: PathBuf) -> Self {
+        Storage { path, ..Default::default() }
+    }
+
+    /// 第 `n` 個備份，1 為最新
+    fn backup_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".bak{}", n));
+        self.path.with_file_name(name)
+    }
+
+    fn current_stamp(&self) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(&self.path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// 設定檔在上次讀寫之後是否被其他程式改過
+    pub fn changed_on_disk(&self) -> bool {
+        self.read_only.is_none() && self.current_stamp() != self.stamp
+    }
+
+    fn read(path: &Path) -> Result<Profiles, String> {
+        let text = fs::read_to_string(path).map_err(|e| tr!("無法讀取 {}: {}", path.display(), e))?;
+        parse(&text)
+    }
+
+    /// 檔案是否為舊版格式，需要寫回才能保留轉換結果（例如新產生的 `id`）
+    fn is_outdated(path: &Path) -> bool {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .is_some_and(|value| version_of(&value) < CURRENT_VERSION)
+    }
+
+    /// 檔案的版本是否比這個程式認得的新
+    fn is_newer(path: &Path) -> bool {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .is_some_and(|value| version_of(&value) > CURRENT_VERSION)
+    }
+
+    /// 把讀不了的主檔改名為 `config.json.corrupt-<時間>` 保留下來
+    fn quarantine(&self) -> Result<PathBuf, String> {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".corrupt-{}", Local::now().format("%Y%m%d%H%M%S")));
+        let target = self.path.with_file_name(name);
+        fs::rename(&self.path, &target).map_err(|e| tr!("無法移開損毀的設定檔: {}", e))?;
+        Ok(target)
+    }
+
+    /// 檔案不存在視為第一次使用。主檔無法使用時先改名保留，再依序嘗試備份；
+    /// 由較新版本寫入的設定檔則保持原樣並停用保存
+    pub fn load(&mut self) -> Result<Loaded, String> {
+        self.stamp = self.current_stamp();
+        if !self.path.exists() {
+            return Ok(Loaded { profiles: None, warning: None });
+        }
+        let error = match Self::read(&self.path) {
+            Ok(profiles) => {
+                let warning = if Self::is_outdated(&self.path) {
+                    self.save(&profiles).err()
+                } else {
+                    self.last_saved = to_json(&profiles).ok();
+                    None
+                };
+                return Ok(Loaded { profiles: Some(profiles), warning });
+            }
+            Err(e) => e,
+        };
+        if Self::is_newer(&self.path) {
+            let error = tr!("{}；為避免覆蓋，已停用保存", error);
+            self.read_only = Some(error.clone());
+            return Err(error);
+        }
+        let moved = match self.quarantine() {
+            Ok(moved) => {
+                self.stamp = None;
+                moved
+            }
+            Err(e) => {
+                let error = tr!("{}；{}，為避免覆蓋，已停用保存", error, e);
+                self.read_only = Some(error.clone());
+                return Err(error);
+            }
+        };
+        let error = tr!("{}；原檔已移到 {}", error, moved.display());
+        for n in 1..=BACKUPS {
+            let backup = self.backup_path(n);
+            if let Ok(profiles) = Self::read(&backup) {
+                let warning = tr!("{}；已改用備份 {}", error, backup.display());
+                return Ok(Loaded { profiles: Some(profiles), warning: Some(warning) });
+            }
+        }
+        Ok(Loaded { profiles: None, warning: Some(error) })
+    }
+
+    /// 內容有變更時才寫入。每次執行第一次寫入前把現有檔案輪替成備份，
+    /// 再先寫入暫存檔、同步到磁碟後改名取代，寫到一半失敗也不會留下殘缺的設定檔
+    pub fn save(&mut self, profiles: &Profiles) -> Result<(), String> {
+        if let Some(reason) = &self.read_only {
+            return Err(reason.clone());
+        }
+        if self.changed_on_disk() {
+            return Err(tr!("設定檔已在其他地方被修改，為避免覆蓋未保存").to_string());
+        }
+        let data = to_json(profiles).map_err(|e| tr!("無法序列化設定: {}", e))?;
+        if self.last_saved.as_ref() == Some(&data) && self.path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| tr!("無法建立 {}: {}", parent.display(), e))?;
+        }
+
+        if self.path.exists() && !self.rotated {
+            for n in (1..BACKUPS).rev() {
+                let from = self.backup_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.backup_path(n + 1)).map_err(|e| tr!("無法輪替備份: {}", e))?;
+                }
+            }
+            fs::copy(&self.path, self.backup_path(1)).map_err(|e| tr!("無法建立備份: {}", e))?;
+            self.rotated = true;
+        }
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let write = |temp: &Path| -> std::io::Result<()> {
+            let mut file = File::create(temp)?;
+            file.write_all(data.as_bytes())?;
+            file.sync_all()
+        };
+        write(&temp).map_err(|e| tr!("無法寫入 {}: {}", temp.display(), e))?;
+        fs::rename(&temp, &self.path).map_err(|e| tr!("無法更新 {}: {}", self.path.display(), e))?;
+        self.last_saved = Some(data);
+        self.stamp = self.current_stamp();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::UserData;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("life_countdown_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn named(name: &str) -> Profiles {
+        Profiles::new(vec![UserData { name: name.into(), ..Default::default() }])
+    }
+
+    #[test]
+    fn migrates_every_older_version() {
+        let v0 = r#"{"birthday":"1990-01-02","life_expectancy":80.0,"name":"小明","gender":"Female","country":""}"#;
+        let profiles = parse(v0).unwrap();
+        assert_eq!(profiles.profiles.len(), 1);
+        assert_eq!(profiles.active().name, "小明");
+        assert_eq!(profiles.active().life_expectancy, Some(80.0));
+
+        let v1 = r#"{"profiles":[{"name":"甲","gender":"Male","country":""},{"name":"乙","gender":"Other","country":""}],"active":7}"#;
+        let profiles = parse(v1).unwrap();
+        assert_eq!(profiles.active, 1);
+        assert_ne!(profiles.profiles[0].id, profiles.profiles[1].id);
+
+        // 舊版檔案讀取後立即寫回，產生的 id 下次讀取仍相同
+        let dir = temp_dir("outdated");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.json"), v1).unwrap();
+        let first = Storage::new(dir.join("config.json")).load().unwrap().profiles.unwrap();
+        let second = Storage::new(dir.join("config.json")).load().unwrap().profiles.unwrap();
+        assert_eq!(first.profiles[1].id, second.profiles[1].id);
+        assert_eq!(fs::read_to_string(Storage::new(dir.join("config.json")).backup_path(1)).unwrap(), v1);
+        let _ = fs::remove_dir_all(&dir);
+
+        let current: Value = serde_json::from_str(&to_json(&profiles).unwrap()).unwrap();
+        assert_eq!(current["version"], CURRENT_VERSION);
+        assert!(parse(r#"{"version":99,"profiles":[],"active":0}"#).is_err());
+        assert!(parse("{").is_err());
+    }
+
+    #[test]
+    fn backups_rotate_once_per_session() {
+        let dir = temp_dir("backups");
+        assert!(Storage::new(dir.join("config.json")).load().unwrap().profiles.is_none());
+
+        for i in 0..BACKUPS + 3 {
+            let mut storage = Storage::new(dir.join("config.json"));
+            storage.load().unwrap();
+            storage.save(&named(&i.to_string())).unwrap();
+            storage.save(&named(&format!("{}!", i))).unwrap();
+        }
+        let mut storage = Storage::new(dir.join("config.json"));
+        let latest = storage.load().unwrap().profiles.unwrap();
+        assert_eq!(latest.active().name, format!("{}!", BACKUPS + 2));
+        assert_eq!(Storage::read(&storage.backup_path(1)).unwrap().active().name, format!("{}!", BACKUPS + 1));
+        assert_eq!(Storage::read(&storage.backup_path(BACKUPS)).unwrap().active().name, "2!");
+        assert!(!storage.backup_path(BACKUPS + 1).exists());
+        assert!(!dir.join("config.json.tmp").exists());
+
+        // 內容沒變時不重寫
+        let modified = fs::metadata(dir.join("config.json")).unwrap().modified().unwrap();
+        storage.save(&latest).unwrap();
+        assert_eq!(fs::metadata(dir.join("config.json")).unwrap().modified().unwrap(), modified);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corrupt_file_is_kept_aside() {
+        let dir = temp_dir("corrupt");
+        let mut storage = Storage::new(dir.join("config.json"));
+        storage.save(&named("備份")).unwrap();
+        let mut storage = Storage::new(dir.join("config.json"));
+        storage.load().unwrap();
+        storage.save(&named("最新")).unwrap();
+        fs::write(dir.join("config.json"), "{ not json").unwrap();
+
+        let mut storage = Storage::new(dir.join("config.json"));
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.profiles.unwrap().active().name, "備份");
+        assert!(loaded.warning.is_some());
+        let corrupt = |dir: &Path| {
+            fs::read_dir(dir)
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("config.json.corrupt-"))
+                .count()
+        };
+        assert_eq!(corrupt(&dir), 1);
+
+        // 沒有可用的備份時從頭開始，損毀的檔案仍然保留
+        for n in 1..=BACKUPS {
+            let _ = fs::remove_file(storage.backup_path(n));
+        }
+        fs::write(dir.join("config.json"), "{ not json").unwrap();
+        let mut storage = Storage::new(dir.join("config.json"));
+        let loaded = storage.load().unwrap();
+        assert!(loaded.profiles.is_none() && loaded.warning.is_some());
+        storage.save(&named("新的")).unwrap();
+        assert!(corrupt(&dir) >= 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_external_changes() {
+        let dir = temp_dir("external");
+        let mut gui = Storage::new(dir.join("config.json"));
+        gui.load().unwrap();
+        gui.save(&named("視窗")).unwrap();
+        assert!(!gui.changed_on_disk());
+
+        let mut cli = Storage::new(dir.join("config.json"));
+        cli.load().unwrap();
+        cli.save(&named("命令列的修改")).unwrap();
+
+        assert!(gui.changed_on_disk());
+        assert!(gui.save(&named("視窗的舊資料")).is_err());
+        assert_eq!(gui.load().unwrap().profiles.unwrap().active().name, "命令列的修改");
+        gui.save(&named("視窗")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn newer_file_is_never_overwritten() {
+        let dir = temp_dir("newer");
+        fs::create_dir_all(&dir).unwrap();
+        let newer = r#"{"version":99,"profiles":[],"active":0}"#;
+        fs::write(dir.join("config.json"), newer).unwrap();
+
+        let mut storage = Storage::new(dir.join("config.json"));
+        assert!(storage.load().is_err());
+        for _ in 0..BACKUPS + 1 {
+            assert!(storage.save(&named("覆蓋")).is_err());
+        }
+        assert_eq!(fs::read_to_string(dir.join("config.json")).unwrap(), newer);
+        assert!(!storage.backup_path(1).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
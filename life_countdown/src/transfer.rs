@@ -0,0 +1,423 @@
+//! 匯入匯出：整包人資料（含目標與日記）存成 JSON 或 CSV，以及把里程碑匯出成 iCalendar
+
+use chrono::{Datelike, Local, NaiveDate};
+use std::fs;
+use std::path::Path;
+
+use crate::goals::{Goal, Recurrence};
+use crate::journal::{self, JournalEntry};
+use crate::profile::{Gender, ProfileId, Profiles, UserData};
+use crate::storage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// 依副檔名判斷，非 `.csv` 一律視為 JSON
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Format::Csv,
+            _ => Format::Json,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// 同一份人資料（識別碼相同）合併目標與日記，其餘新增
+    #[default]
+    Merge,
+    /// 以匯入內容取代全部人資料
+    Overwrite,
+}
+
+pub fn export(profiles: &Profiles, path: &Path) -> Result<(), String> {
+    let data = match Format::from_path(path) {
+        Format::Json => storage::to_json(profiles).map_err(|e| tr!("無法序列化資料: {}", e))?,
+        Format::Csv => to_csv(profiles),
+    };
+    fs::write(path, data).map_err(|e| tr!("無法寫入 {}: {}", path.display(), e))
+}
+
+pub fn import(path: &Path) -> Result<Profiles, String> {
+    let text = fs::read_to_string(path).map_err(|e| tr!("無法讀取 {}: {}", path.display(), e))?;
+    match Format::from_path(path) {
+        Format::Json => storage::parse(&text),
+        Format::Csv => from_csv(&text),
+    }
+}
+
+/// 把匯入的人資料併入 `profiles`，回傳新增的目標與日記數
+pub fn apply(profiles: &mut Profiles, imported: Profiles, mode: ImportMode) -> (usize, usize) {
+    let count = |p: &Profiles| p.profiles.iter().map(|u| (u.goals.len(), u.journal.len())).fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    if mode == ImportMode::Overwrite {
+        let added = count(&imported);
+        profiles.profiles = imported.profiles;
+        profiles.active = imported.active;
+        return added;
+    }
+
+    let (mut goals, mut entries) = (0, 0);
+    for incoming in imported.profiles {
+        let Some(existing) = profiles.profiles.iter_mut().find(|p| p.id == incoming.id) else {
+            goals += incoming.goals.len();
+            entries += incoming.journal.len();
+            profiles.profiles.push(incoming);
+            continue;
+        };
+        existing.birthday = existing.birthday.or(incoming.birthday);
+        for goal in incoming.goals {
+            if !existing.goals.iter().any(|g| g.title == goal.title && g.date == goal.date) {
+                existing.goals.push(goal);
+                goals += 1;
+            }
+        }
+        // 同一天已有日記時保留本機的內容
+        for (date, entry) in incoming.journal {
+            if let std::collections::btree_map::Entry::Vacant(slot) = existing.journal.entry(date) {
+                slot.insert(entry);
+                entries += 1;
+            }
+        }
+    }
+    (goals, entries)
+}
+
+const CSV_HEADER: [&str; 14] = [
+    "type", "profile", "id", "birthday", "gender", "country", "life_expectancy",
+    "date", "title", "recurrence", "completed", "mood", "tags", "text",
+];
+
+/// `id` 欄位之前的版本匯出的 CSV 沒有這欄，可省略
+const OPTIONAL_COLUMN: usize = 2;
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 解析 CSV，支援引號內的逗號、換行與 `""`
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+fn gender_name(gender: &Gender) -> &'static str {
+    match gender {
+        Gender::Male => "Male",
+        Gender::Female => "Female",
+        Gender::Other => "Other",
+    }
+}
+
+fn recurrence_name(recurrence: Recurrence) -> &'static str {
+    match recurrence {
+        Recurrence::Once => "Once",
+        Recurrence::Weekly => "Weekly",
+        Recurrence::Monthly => "Monthly",
+        Recurrence::Yearly => "Yearly",
+    }
+}
+
+/// 每列一筆資料，以 `type` 欄區分 profile、goal 與 journal；用不到的欄位留空
+pub fn to_csv(profiles: &Profiles) -> String {
+    let mut out = CSV_HEADER.join(",") + "\n";
+    let mut push = |fields: [String; 14]| {
+        out += &fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+        out.push('\n');
+    };
+    let date = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+    for profile in &profiles.profiles {
+        let (name, id) = (profile.name.clone(), profile.id.0.clone());
+        push([
+            "profile".into(), name.clone(), id.clone(), date(profile.birthday), gender_name(&profile.gender).into(),
+            profile.country.clone(), profile.life_expectancy.map(|e| e.to_string()).unwrap_or_default(),
+            String::new(), String::new(), String::new(), String::new(), String::new(), String::new(), String::new(),
+        ]);
+        for goal in &profile.goals {
+            push([
+                "goal".into(), name.clone(), id.clone(), String::new(), String::new(), String::new(), String::new(),
+                goal.date.to_string(), goal.title.clone(), recurrence_name(goal.recurrence).into(), date(goal.completed),
+                String::new(), String::new(), String::new(),
+            ]);
+        }
+        for (day, entry) in &profile.journal {
+            push([
+                "journal".into(), name.clone(), id.clone(), String::new(), String::new(), String::new(), String::new(),
+                day.to_string(), String::new(), String::new(), String::new(),
+                entry.mood.to_string(), entry.tags.join(", "), entry.text.clone(),
+            ]);
+        }
+    }
+    out
+}
+
+pub fn from_csv(text: &str) -> Result<Profiles, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header = rows.next().ok_or(tr!("CSV 是空的"))?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name).ok_or(tr!("CSV 缺少欄位 {}", name));
+    let columns: Vec<Option<usize>> = CSV_HEADER
+        .iter()
+        .enumerate()
+        .map(|(i, name)| match column(name) {
+            Ok(index) => Ok(Some(index)),
+            Err(_) if i == OPTIONAL_COLUMN => Ok(None),
+            Err(e) => Err(e),
+        })
+        .collect::<Result<_, _>>()?;
+
+    // 依識別碼分組；沒有識別碼的舊檔案退回以姓名分組
+    let mut keys: Vec<String> = Vec::new();
+    let mut profiles: Vec<UserData> = Vec::new();
+    for (line, row) in rows.enumerate() {
+        let line = line + 2;
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let get = |i: usize| columns[i].and_then(|column| row.get(column)).map(String::as_str).unwrap_or("").trim();
+        let date = |i: usize| -> Result<Option<NaiveDate>, String> {
+            match get(i) {
+                "" => Ok(None),
+                text => NaiveDate::parse_from_str(text, "%Y-%m-%d").map(Some).map_err(|_| tr!("第 {} 列日期格式錯誤: {}", line, text)),
+            }
+        };
+        let name = get(1).to_string();
+        let key = if get(2).is_empty() { format!("name:{}", name) } else { get(2).to_string() };
+        let index = match keys.iter().position(|k| *k == key) {
+            Some(index) => index,
+            None => {
+                let id = if get(2).is_empty() { ProfileId::default() } else { ProfileId(get(2).to_string()) };
+                keys.push(key);
+                profiles.push(UserData { id, name, ..Default::default() });
+                profiles.len() - 1
+            }
+        };
+        match get(0) {
+            "profile" => {
+                let profile = &mut profiles[index];
+                profile.birthday = date(3)?;
+                profile.gender = match get(4) {
+                    "Female" => Gender::Female,
+                    "Other" => Gender::Other,
+                    _ => Gender::Male,
+                };
+                profile.country = get(5).to_string();
+                profile.life_expectancy = get(6).parse().ok();
+            }
+            "goal" => {
+                let recurrence = Recurrence::ALL.into_iter().find(|&r| recurrence_name(r) == get(9)).unwrap_or_default();
+                let mut goal = Goal::new(get(8).to_string(), date(7)?.ok_or(tr!("第 {} 列缺少日期", line))?, recurrence);
+                goal.completed = date(10)?;
+                profiles[index].goals.push(goal);
+            }
+            "journal" => {
+                let day = date(7)?.ok_or(tr!("第 {} 列缺少日期", line))?;
+                let mood = get(11).parse::<u8>().unwrap_or(3).clamp(1, 5);
+                let text = columns[13].and_then(|column| row.get(column)).cloned().unwrap_or_default();
+                let entry = JournalEntry { text, mood, tags: journal::parse_tags(get(12)) };
+                profiles[index].journal.insert(day, entry);
+            }
+            other => return Err(tr!("第 {} 列類型不明: {}", line, other)),
+        }
+    }
+    if profiles.is_empty() {
+        return Err(tr!("CSV 沒有任何人資料").to_string());
+    }
+    Ok(Profiles::new(profiles))
+}
+
+/// iCalendar 文字值需跳脫反斜線、分號、逗號與換行
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// 每行不超過 75 位元組，續行以空白開頭
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out += "\r\n ";
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out + "\r\n"
+}
+
+/// 重複目標的 RRULE。App 把月底的日期併到短月份的最後一天，RFC 5545 則會跳過沒有那天的月份，
+/// 所以 29～31 日的每月目標與 2 月 29 日的每年目標改用 `BYMONTHDAY=-1` 指定當月最後一天
+fn rrule(goal: &Goal) -> Option<String> {
+    let day = goal.date.day();
+    let rule = match goal.recurrence {
+        Recurrence::Once => return None,
+        Recurrence::Weekly => "WEEKLY".to_string(),
+        Recurrence::Monthly if day == 31 => "MONTHLY;BYMONTHDAY=-1".to_string(),
+        Recurrence::Monthly if day >= 29 => format!("MONTHLY;BYMONTHDAY={},-1;BYSETPOS=1", day),
+        Recurrence::Monthly => "MONTHLY".to_string(),
+        Recurrence::Yearly if goal.date.month() == 2 && day == 29 => "YEARLY;BYMONTH=2;BYMONTHDAY=-1".to_string(),
+        Recurrence::Yearly => "YEARLY".to_string(),
+    };
+    Some(rule)
+}
+
+/// 目標與預期壽命終點的全天事件；重複的目標帶上 RRULE
+pub fn to_ics(user: &UserData) -> String {
+    let stamp = Local::now().naive_utc().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec!["BEGIN:VCALENDAR".to_string(), "VERSION:2.0".into(), "PRODID:-//life_countdown//ZH".into()];
+    let mut event = |uid: String, date: NaiveDate, summary: String, rule: Option<String>| {
+        lines.push("BEGIN:VEVENT".into());
+        lines.push(format!("UID:{}@life_countdown", uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+        if let Some(end) = date.succ_opt() {
+            lines.push(format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+        }
+        lines.push(format!("SUMMARY:{}", ics_text(&summary)));
+        if let Some(rule) = rule {
+            lines.push(format!("RRULE:FREQ={}", rule));
+        }
+        lines.push("END:VEVENT".into());
+    };
+
+    let today = Local::now().date_naive();
+    for (index, goal) in user.goals.iter().enumerate() {
+        // 從下一次尚未完成的日期開始，已完成的部分不再出現在日曆上
+        let Some(start) = goal.next_occurrence(today) else {
+            continue;
+        };
+        event(format!("goal-{}-{}", index, goal.date.format("%Y%m%d")), start, format!("🎯 {}", goal.title), rrule(goal));
+    }
+    if let Some(end) = user.expected_death_date() {
+        event(format!("expectancy-{}", end.format("%Y%m%d")), end, tr!("⏳ {} 的預期壽命終點", user.display_name()), None);
+    }
+    lines.push("END:VCALENDAR".into());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Profiles {
+        let mut user = UserData {
+            name: "王, 小明".into(),
+            birthday: NaiveDate::from_ymd_opt(1990, 1, 2),
+            gender: Gender::Female,
+            country: "TW".into(),
+            life_expectancy: Some(85.0),
+            ..Default::default()
+        };
+        user.goals.push(Goal::new("跑\"全馬\"".into(), NaiveDate::from_ymd_opt(2030, 5, 1).unwrap(), Recurrence::Yearly));
+        let entry = JournalEntry { text: "第一行\n第二行, 有逗號".into(), mood: 4, tags: vec!["家人".into()] };
+        user.journal.insert(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), entry);
+        Profiles::new(vec![user, UserData { name: "另一位".into(), ..Default::default() }])
+    }
+
+    #[test]
+    fn csv_round_trips_profiles_goals_and_journal() {
+        let original = sample();
+        let parsed = from_csv(&to_csv(&original)).unwrap();
+        assert_eq!(parsed.profiles.len(), 2);
+        let (a, b) = (&original.profiles[0], &parsed.profiles[0]);
+        assert_eq!((&a.name, a.birthday, &a.gender, &a.country, a.life_expectancy), (&b.name, b.birthday, &b.gender, &b.country, b.life_expectancy));
+        assert_eq!(b.goals[0].title, a.goals[0].title);
+        assert_eq!(b.goals[0].recurrence, Recurrence::Yearly);
+        assert_eq!(b.journal, a.journal);
+        assert!(from_csv("type,profile\ngoal,x\n").is_err());
+    }
+
+    #[test]
+    fn merge_keeps_local_entries_and_adds_new_ones() {
+        let mut local = sample();
+        let mut incoming = local.clone();
+        let day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        incoming.profiles[0].journal.get_mut(&day).unwrap().text = "遠端".into();
+        incoming.profiles[0].journal.insert(day.succ_opt().unwrap(), JournalEntry { text: "新".into(), mood: 3, tags: vec![] });
+        incoming.profiles.push(UserData { name: "第三位".into(), ..Default::default() });
+
+        assert_eq!(apply(&mut local, incoming.clone(), ImportMode::Merge), (0, 1));
+        assert_eq!(local.profiles.len(), 3);
+        assert_ne!(local.profiles[0].journal[&day].text, "遠端");
+
+        assert_eq!(apply(&mut local, incoming, ImportMode::Overwrite), (1, 2));
+        assert_eq!(local.profiles[0].journal[&day].text, "遠端");
+    }
+
+    #[test]
+    fn unnamed_profiles_stay_separate() {
+        let birthday = |y| NaiveDate::from_ymd_opt(y, 1, 1);
+        let mut local = Profiles::new(vec![UserData { birthday: birthday(1990), ..Default::default() }]);
+        let other = UserData { birthday: birthday(2000), ..Default::default() };
+        let imported = from_csv(&to_csv(&Profiles::new(vec![local.profiles[0].clone(), other]))).unwrap();
+        assert_eq!(imported.profiles.len(), 2);
+
+        apply(&mut local, imported, ImportMode::Merge);
+        assert_eq!(local.profiles.len(), 2);
+        assert_eq!(local.profiles[1].birthday, birthday(2000));
+
+        // 沒有 id 欄的舊 CSV 仍可匯入
+        let legacy = "type,profile,birthday,gender,country,life_expectancy,date,title,recurrence,completed,mood,tags,text\nprofile,甲,1990-01-02,Male,TW,,,,,,,,\n";
+        assert_eq!(from_csv(legacy).unwrap().active().birthday, NaiveDate::from_ymd_opt(1990, 1, 2));
+    }
+
+    #[test]
+    fn ics_has_escaped_folded_events() {
+        let ics = to_ics(&sample().profiles[0]);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("RRULE:FREQ=YEARLY\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20300501"));
+        assert!(ics.contains("王\\, 小明"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    }
+
+    #[test]
+    fn ics_recurrence_follows_completion_and_month_ends() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut paid = Goal::new("繳費".into(), date(2000, 1, 31), Recurrence::Monthly);
+        paid.completed = Some(date(2099, 3, 31));
+        let leap = Goal::new("閏日".into(), date(2000, 2, 29), Recurrence::Yearly);
+        let rent = Goal::new("房租".into(), date(2000, 1, 30), Recurrence::Monthly);
+        let user = UserData { goals: vec![paid, leap, rent], ..Default::default() };
+        let ics = to_ics(&user);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20990430\r\n"));
+        assert!(ics.contains("RRULE:FREQ=MONTHLY;BYMONTHDAY=-1\r\n"));
+        assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1\r\n"));
+        assert!(ics.contains("RRULE:FREQ=MONTHLY;BYMONTHDAY=30,-1;BYSETPOS=1\r\n"));
+        assert!(!ics.contains("DTSTART;VALUE=DATE:20000131"));
+    }
+}
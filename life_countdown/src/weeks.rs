@@ -0,0 +1,285 @@
+//! 「人生週曆」：每列一年，每格一週（或一個月），標出已度過、本週與目標
+
+use chrono::{Datelike, Months, NaiveDate};
+use eframe::egui;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+use crate::goals::Goal;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum GridUnit {
+    #[default]
+    Weeks,
+    Months,
+}
+
+impl GridUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            GridUnit::Weeks => tr!("週"),
+            GridUnit::Months => tr!("月"),
+        }
+    }
+
+    fn columns(self) -> usize {
+        match self {
+            GridUnit::Weeks => 52,
+            GridUnit::Months => 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Lived,
+    Current,
+    Future,
+}
+
+const LIVED: [u8; 3] = [100, 149, 237];
+const CURRENT: [u8; 3] = [255, 140, 0];
+const FUTURE: [u8; 3] = [60, 60, 60];
+const MILESTONE: [u8; 3] = [255, 215, 0];
+const BACKGROUND: [u8; 3] = [27, 27, 27];
+
+fn color(rgb: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2])
+}
+
+/// 滿 `years` 歲的那天；2 月 29 日出生者在平年落在 2 月 28 日
+fn anniversary(birthday: NaiveDate, years: u32) -> Option<NaiveDate> {
+    birthday.checked_add_months(Months::new(years.checked_mul(12)?))
+}
+
+/// `date` 落在第幾列（歲數）第幾格；出生前為 None，每年最後幾天併入第 52 週
+pub fn position(birthday: NaiveDate, date: NaiveDate, unit: GridUnit) -> Option<(usize, usize)> {
+    if date < birthday {
+        return None;
+    }
+    let mut years = (date.year() - birthday.year()) as u32;
+    if anniversary(birthday, years)? > date {
+        years -= 1;
+    }
+    let start = anniversary(birthday, years)?;
+    let column = match unit {
+        GridUnit::Weeks => ((date - start).num_days() / 7) as usize,
+        GridUnit::Months => {
+            let months = (date.year() - start.year()) * 12 + date.month() as i32 - start.month() as i32;
+            let months = if start.checked_add_months(Months::new(months as u32))? > date { months - 1 } else { months };
+            months as usize
+        }
+    };
+    Some((years as usize, column.min(unit.columns() - 1)))
+}
+
+/// 預期壽命之後最多再多畫幾列給較晚的目標
+const EXTRA_ROWS: usize = 5;
+
+struct Milestone {
+    at: (usize, usize),
+    title: String,
+    date: NaiveDate,
+    /// 目標落在格子範圍之後，畫在最後一列的下緣
+    beyond: bool,
+}
+
+pub struct LifeGrid {
+    pub unit: GridUnit,
+    pub rows: usize,
+    current: Option<(usize, usize)>,
+    milestones: Vec<Milestone>,
+    birthday: NaiveDate,
+}
+
+impl LifeGrid {
+    pub fn new(birthday: NaiveDate, today: NaiveDate, life_expectancy: f32, unit: GridUnit, goals: &[Goal]) -> Self {
+        let current = position(birthday, today, unit);
+        let positions: Vec<_> = goals
+            .iter()
+            .filter_map(|goal| {
+                let date = goal.next_occurrence(today).unwrap_or(goal.date);
+                Some((position(birthday, date, unit)?, goal.title.clone(), date))
+            })
+            .collect();
+        // 列數以預期壽命為準，較晚的目標最多多畫幾列，更遠的只在下緣標示
+        let lifespan = (life_expectancy.ceil().max(1.0) as usize).max(current.map_or(0, |(row, _)| row + 1));
+        let last_goal = positions
+            .iter()
+            .map(|&((row, _), _, _)| row + 1)
+            .filter(|&rows| rows <= lifespan + EXTRA_ROWS)
+            .max()
+            .unwrap_or(0);
+        let rows = last_goal.max(lifespan);
+        let milestones = positions
+            .into_iter()
+            .map(|((row, column), title, date)| Milestone { at: (row.min(rows - 1), column), title, date, beyond: row >= rows })
+            .collect();
+        LifeGrid { unit, rows, current, milestones, birthday }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.unit.columns()
+    }
+
+    pub fn cell(&self, row: usize, column: usize) -> Cell {
+        match self.current {
+            Some(current) if (row, column) == current => Cell::Current,
+            Some(current) if (row, column) < current => Cell::Lived,
+            _ => Cell::Future,
+        }
+    }
+
+    fn cell_color(&self, row: usize, column: usize) -> [u8; 3] {
+        match self.cell(row, column) {
+            Cell::Lived => LIVED,
+            Cell::Current => CURRENT,
+            Cell::Future => FUTURE,
+        }
+    }
+
+    fn has_milestone(&self, row: usize, column: usize) -> bool {
+        self.milestones.iter().any(|milestone| !milestone.beyond && milestone.at == (row, column))
+    }
+
+    /// 是否有超出格子範圍的目標要標在這格下緣
+    fn has_marker(&self, row: usize, column: usize) -> bool {
+        self.milestones.iter().any(|milestone| milestone.beyond && milestone.at == (row, column))
+    }
+
+    /// 在 `rect` 中畫出整個格子，回傳滑鼠所在格子的說明
+    pub fn paint(&self, ui: &mut egui::Ui, rect: egui::Rect) -> Option<String> {
+        let painter = ui.painter_at(rect);
+        let size = rect.width() / self.columns() as f32;
+        let gap = (size * 0.15).max(1.0);
+        let hover = ui.ctx().pointer_hover_pos().filter(|pos| rect.contains(*pos));
+        let mut hovered = None;
+
+        for row in 0..self.rows {
+            for column in 0..self.columns() {
+                let min = rect.min + egui::vec2(column as f32 * size, row as f32 * size);
+                let cell = egui::Rect::from_min_size(min, egui::vec2(size - gap, size - gap));
+                painter.rect_filled(cell, 1.0, color(self.cell_color(row, column)));
+                if self.has_milestone(row, column) {
+                    painter.rect_stroke(cell.expand(gap / 2.0), 1.0, egui::Stroke::new(gap.max(1.5), color(MILESTONE)));
+                }
+                if self.has_marker(row, column) {
+                    let points = vec![cell.left_bottom(), cell.right_bottom(), cell.center_bottom() - egui::vec2(0.0, cell.height() * 0.6)];
+                    painter.add(egui::Shape::convex_polygon(points, color(MILESTONE), egui::Stroke::NONE));
+                }
+                if hover.is_some_and(|pos| egui::Rect::from_min_size(min, egui::vec2(size, size)).contains(pos)) {
+                    hovered = Some(self.describe(row, column));
+                }
+            }
+        }
+        hovered
+    }
+
+    fn describe(&self, row: usize, column: usize) -> String {
+        let mut text = tr!("{} 歲，第 {} {}", row, column + 1, self.unit.label());
+        if let Some(start) = anniversary(self.birthday, row as u32) {
+            let date = match self.unit {
+                GridUnit::Weeks => start.checked_add_signed(chrono::Duration::weeks(column as i64)),
+                GridUnit::Months => start.checked_add_months(Months::new(column as u32)),
+            };
+            if let Some(date) = date {
+                text += &tr!("（{} 起）", date);
+            }
+        }
+        for milestone in &self.milestones {
+            if milestone.at == (row, column) {
+                text += &format!("\n🎯 {} ({})", milestone.title, milestone.date);
+                if milestone.beyond {
+                    text += tr!(" ↓ 超出格子範圍");
+                }
+            }
+        }
+        text
+    }
+
+    /// 畫成海報大小的 PNG：每格 `cell` 像素，格間留 1 像素，外圍留白
+    pub fn to_image(&self, cell: u32) -> RgbaImage {
+        let margin = cell * 2;
+        let pitch = cell + 1;
+        let width = margin * 2 + pitch * self.columns() as u32;
+        let height = margin * 2 + pitch * self.rows as u32;
+        let [r, g, b] = BACKGROUND;
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]));
+        for row in 0..self.rows {
+            for column in 0..self.columns() {
+                let milestone = self.has_milestone(row, column);
+                let marker = self.has_marker(row, column);
+                let [r, g, b] = self.cell_color(row, column);
+                let (x0, y0) = (margin + column as u32 * pitch, margin + row as u32 * pitch);
+                for y in 0..cell {
+                    for x in 0..cell {
+                        let border = x == 0 || y == 0 || x + 1 == cell || y + 1 == cell;
+                        let bottom = y * 2 >= cell;
+                        let pixel = if (milestone && border) || (marker && bottom) { MILESTONE } else { [r, g, b] };
+                        image.put_pixel(x0 + x, y0 + y, Rgba([pixel[0], pixel[1], pixel[2], 255]));
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    pub fn export_png(&self, path: &Path) -> Result<(), String> {
+        self.to_image(12).save(path).map_err(|e| tr!("無法匯出圖片: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn positions_follow_birthdays() {
+        let birthday = date(1990, 5, 20);
+        assert_eq!(position(birthday, birthday, GridUnit::Weeks), Some((0, 0)));
+        assert_eq!(position(birthday, date(1990, 5, 27), GridUnit::Weeks), Some((0, 1)));
+        assert_eq!(position(birthday, date(1991, 5, 19), GridUnit::Weeks), Some((0, 51)));
+        assert_eq!(position(birthday, date(1991, 5, 20), GridUnit::Weeks), Some((1, 0)));
+        assert_eq!(position(birthday, date(2000, 6, 19), GridUnit::Months), Some((10, 0)));
+        assert_eq!(position(birthday, date(2000, 6, 20), GridUnit::Months), Some((10, 1)));
+        assert_eq!(position(birthday, date(1989, 1, 1), GridUnit::Weeks), None);
+    }
+
+    #[test]
+    fn leap_day_birthday_rolls_over_on_february_28() {
+        let birthday = date(2000, 2, 29);
+        assert_eq!(position(birthday, date(2001, 2, 27), GridUnit::Months), Some((0, 11)));
+        assert_eq!(position(birthday, date(2001, 2, 28), GridUnit::Months), Some((1, 0)));
+    }
+
+    #[test]
+    fn cells_before_today_are_lived() {
+        let goals = [Goal::new("退休".into(), date(2055, 1, 1), crate::goals::Recurrence::Once)];
+        let grid = LifeGrid::new(date(1990, 1, 1), date(2020, 1, 15), 80.0, GridUnit::Weeks, &goals);
+        assert_eq!(grid.rows, 80);
+        assert_eq!(grid.cell(29, 51), Cell::Lived);
+        assert_eq!(grid.cell(30, 2), Cell::Current);
+        assert_eq!(grid.cell(30, 3), Cell::Future);
+        assert_eq!(grid.describe(65, 0).lines().count(), 2);
+        let image = grid.to_image(4);
+        assert_eq!(image.width(), 4 * 2 * 2 + 5 * 52);
+    }
+
+    #[test]
+    fn distant_goals_do_not_grow_the_grid() {
+        let goals = [
+            Goal::new("晚年".into(), date(2072, 1, 1), crate::goals::Recurrence::Once),
+            Goal::new("遙遠".into(), date(9999, 1, 1), crate::goals::Recurrence::Once),
+        ];
+        let grid = LifeGrid::new(date(1990, 1, 1), date(2020, 1, 15), 80.0, GridUnit::Weeks, &goals);
+        assert_eq!(grid.rows, 83);
+        assert!(grid.has_milestone(82, 0));
+        assert!(grid.has_marker(82, 0));
+        assert!(grid.describe(82, 0).contains("遙遠"));
+        assert_eq!(grid.to_image(1).height(), 2 * 2 + 2 * 83);
+    }
+}
@@ -1,257 +1,159 @@
-use std::collections::HashMap;
-use std::rc::Rc;
+//! 大尺度檢視使用的 HashLife 宇宙：以原點為中心，x 向右、y 向下
+//!
+//! 演化交給 hashlife_br 的 [`ParallelUniverse`]，支援任意 B/S 規則，節點表超過記憶體預算時
+//! 在步與步之間回收。節點以 `Arc` 共享，整個宇宙可以交給模擬執行緒推進，UI 只保留根節點繪製；
+//! 這裡只把錯誤轉成介面上的訊息。
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct QuadTree {
-    level: u8,
-    nw: Option<Rc<QuadTree>>,
-    ne: Option<Rc<QuadTree>>,
-    sw: Option<Rc<QuadTree>>,
-    se: Option<Rc<QuadTree>>,
-    alive: bool,
-}
+use std::sync::Arc;
+
+use hashlife_br::{ParallelNode, ParallelUniverse, RangeError, Rule, Universe};
 
 pub struct HashLifeUniverse {
-    root: Rc<QuadTree>,
-    cache: HashMap<(Rc<QuadTree>, u32), Rc<QuadTree>>,
-    node_cache: HashMap<(u8, Option<Rc<QuadTree>>, Option<Rc<QuadTree>>, Option<Rc<QuadTree>>, Option<Rc<QuadTree>>), Rc<QuadTree>>,
-    generation: u64,
+    inner: ParallelUniverse,
 }
 
-impl QuadTree {
-    pub fn new_cell(alive: bool) -> Rc<Self> {
-        Rc::new(QuadTree {
-            level: 0,
-            nw: None,
-            ne: None,
-            sw: None,
-            se: None,
-            alive,
-        })
-    }
-
-    pub fn new_node(level: u8, nw: Rc<QuadTree>, ne: Rc<QuadTree>, sw: Rc<QuadTree>, se: Rc<QuadTree>) -> Rc<Self> {
-        Rc::new(QuadTree {
-            level,
-            nw: Some(nw),
-            ne: Some(ne),
-            sw: Some(sw),
-            se: Some(se),
-            alive: false,
-        })
-    }
-
-    pub fn size(&self) -> u32 {
-        1 << self.level
+impl Default for HashLifeUniverse {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn population(&self) -> u64 {
-        if self.level == 0 {
-            if self.alive { 1 } else { 0 }
-        } else {
-            self.nw.as_ref().unwrap().population() +
-            self.ne.as_ref().unwrap().population() +
-            self.sw.as_ref().unwrap().population() +
-            self.se.as_ref().unwrap().population()
-        }
-    }
+fn range_error(_: RangeError) -> String {
+    format!("圖樣超出可模擬的範圍（邊長 2^{}）", Universe::MAX_LEVEL)
 }
 
 impl HashLifeUniverse {
     pub fn new() -> Self {
-        let dead_cell = QuadTree::new_cell(false);
-        let root = QuadTree::new_node(2, dead_cell.clone(), dead_cell.clone(), dead_cell.clone(), dead_cell);
-        
-        HashLifeUniverse {
-            root,
-            cache: HashMap::new(),
-            node_cache: HashMap::new(),
-            generation: 0,
-        }
+        Self::with_rule(Rule::default())
     }
 
-    fn get_node(&mut self, level: u8, nw: Rc<QuadTree>, ne: Rc<QuadTree>, sw: Rc<QuadTree>, se: Rc<QuadTree>) -> Rc<QuadTree> {
-        let key = (level, Some(nw.clone()), Some(ne.clone()), Some(sw.clone()), Some(se.clone()));
-        
-        if let Some(node) = self.node_cache.get(&key) {
-            node.clone()
-        } else {
-            let node = QuadTree::new_node(level, nw, ne, sw, se);
-            self.node_cache.insert(key, node.clone());
-            node
+    pub fn with_rule(rule: Rule) -> Self {
+        HashLifeUniverse {
+            inner: ParallelUniverse::with_rule(rule),
         }
     }
 
-    fn expand(&mut self) {
-        let level = self.root.level + 1;
-        let dead_quad = self.empty_quad(self.root.level);
-        
-        // 先提取出當前根節點的四個子節點
-        let old_nw = self.root.nw.as_ref().unwrap().clone();
-        let old_ne = self.root.ne.as_ref().unwrap().clone();
-        let old_sw = self.root.sw.as_ref().unwrap().clone();
-        let old_se = self.root.se.as_ref().unwrap().clone();
-        
-        // 然後分別創建新的子節點
-        let new_nw = self.get_node(level - 1, dead_quad.clone(), dead_quad.clone(), dead_quad.clone(), old_nw);
-        let new_ne = self.get_node(level - 1, dead_quad.clone(), dead_quad.clone(), old_ne, dead_quad.clone());
-        let new_sw = self.get_node(level - 1, dead_quad.clone(), old_sw, dead_quad.clone(), dead_quad.clone());
-        let new_se = self.get_node(level - 1, old_se, dead_quad.clone(), dead_quad.clone(), dead_quad.clone());
-        
-        // 最後創建新的根節點
-        self.root = self.get_node(level, new_nw, new_ne, new_sw, new_se);
-    }
-    fn empty_quad(&mut self, level: u8) -> Rc<QuadTree> {
-        if level == 0 {
-            QuadTree::new_cell(false)
-        } else {
-            let sub = self.empty_quad(level - 1);
-            self.get_node(level, sub.clone(), sub.clone(), sub.clone(), sub)
-        }
+    /// 設定 (x, y) 的生死；超出範圍時宇宙內容不變
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<(), String> {
+        self.inner.set_cell(x, y, alive).map_err(range_error)
     }
 
-    fn next_generation(&mut self, node: &Rc<QuadTree>, steps: u32) -> Rc<QuadTree> {
-        if steps == 0 {
-            return node.clone();
-        }
-
-        let cache_key = (node.clone(), steps);
-        if let Some(cached) = self.cache.get(&cache_key) {
-            return cached.clone();
-        }
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        self.inner.get_cell(x, y)
+    }
 
-        let result = if node.level == 2 {
-            self.next_gen_4x4(node)
-        } else {
-            // 簡化版本 - 只處理一步
-            let nw = self.next_generation(node.nw.as_ref().unwrap(), 1);
-            let ne = self.next_generation(node.ne.as_ref().unwrap(), 1);
-            let sw = self.next_generation(node.sw.as_ref().unwrap(), 1);
-            let se = self.next_generation(node.se.as_ref().unwrap(), 1);
-            
-            self.get_node(node.level - 1, nw, ne, sw, se)
-        };
+    /// 前進 `generations` 代；圖樣長出範圍時停在最後一次完成的跳躍，世代數與內容仍一致
+    pub fn step(&mut self, generations: u64) -> Result<(), String> {
+        self.inner.step(generations).map_err(range_error)
+    }
 
-        self.cache.insert(cache_key, result.clone());
-        result
+    pub fn generation(&self) -> u64 {
+        self.inner.generation()
     }
 
-    fn next_gen_4x4(&mut self, node: &Rc<QuadTree>) -> Rc<QuadTree> {
-        // 簡化版本 - 直接返回當前狀態
-        let nw = node.nw.as_ref().unwrap();
-        let ne = node.ne.as_ref().unwrap();
-        let sw = node.sw.as_ref().unwrap();
-        let se = node.se.as_ref().unwrap();
-        
-        self.get_node(1, nw.clone(), ne.clone(), sw.clone(), se.clone())
+    pub fn root(&self) -> &Arc<ParallelNode> {
+        self.inner.root()
     }
 
-    pub fn set_cell(&mut self, x: i32, y: i32, alive: bool) {
-        let mut root = self.root.clone();
-        let size = root.size() as i32;
-        let half_size = size / 2;
+    pub fn rule(&self) -> &Rule {
+        self.inner.rule()
+    }
 
-        while x < -half_size || x >= half_size || y < -half_size || y >= half_size {
-            self.expand();
-            root = self.root.clone();
-        }
-        
-        let new_root = self.set_cell_recursive(&root, x, y, alive, 0, 0);
-        self.root = new_root;
+    /// 清空所有細胞並回到第 0 代，規則不變
+    pub fn clear(&mut self) {
+        *self = Self::with_rule(self.rule().clone());
     }
 
-    fn set_cell_recursive(&mut self, node: &Rc<QuadTree>, x: i32, y: i32, alive: bool, node_x: i32, node_y: i32) -> Rc<QuadTree> {
-        if node.level == 0 {
-            return QuadTree::new_cell(alive);
-        }
-        
-        let half_size = (node.size() / 2) as i32;
-        let (new_nw, new_ne, new_sw, new_se) = if x < node_x {
-            if y < node_y {
-                (self.set_cell_recursive(node.nw.as_ref().unwrap(), x, y, alive, node_x - half_size, node_y - half_size),
-                 node.ne.as_ref().unwrap().clone(),
-                 node.sw.as_ref().unwrap().clone(),
-                 node.se.as_ref().unwrap().clone())
-            } else {
-                (node.nw.as_ref().unwrap().clone(),
-                 node.ne.as_ref().unwrap().clone(),
-                 self.set_cell_recursive(node.sw.as_ref().unwrap(), x, y, alive, node_x - half_size, node_y + half_size),
-                 node.se.as_ref().unwrap().clone())
-            }
-        } else {
-            if y < node_y {
-                (node.nw.as_ref().unwrap().clone(),
-                 self.set_cell_recursive(node.ne.as_ref().unwrap(), x, y, alive, node_x + half_size, node_y - half_size),
-                 node.sw.as_ref().unwrap().clone(),
-                 node.se.as_ref().unwrap().clone())
-            } else {
-                (node.nw.as_ref().unwrap().clone(),
-                 node.ne.as_ref().unwrap().clone(),
-                 node.sw.as_ref().unwrap().clone(),
-                 self.set_cell_recursive(node.se.as_ref().unwrap(), x, y, alive, node_x + half_size, node_y + half_size))
-            }
-        };
-        
-        self.get_node(node.level, new_nw, new_ne, new_sw, new_se)
+    /// 讀取 Golly 的 macrocell（.mc）格式，可描述遠大於記憶體所能展開的圖樣，規則取自檔案的 `#R` 行
+    pub fn from_macrocell(text: &str) -> Result<Self, String> {
+        let inner = ParallelUniverse::read_macrocell(text.as_bytes()).map_err(|e| format!("macrocell 格式錯誤: {}", e))?;
+        Ok(HashLifeUniverse { inner })
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashLifeUniverse;
+    use crate::{Cell, Universe};
+    use hashlife_br::Rule;
 
-    pub fn get_cell(&self, x: i32, y: i32) -> bool {
-        let size = self.root.size() as i32;
-        let half_size = size / 2;
-        
-        if x < -half_size || x >= half_size || y < -half_size || y >= half_size {
-            return false;
+    fn from_cells(cells: &[(i64, i64)]) -> HashLifeUniverse {
+        let mut universe = HashLifeUniverse::new();
+        for &(x, y) in cells {
+            universe.set_cell(x, y, true).unwrap();
         }
-        
-        self.get_cell_recursive(&self.root, x, y, 0, 0)
+        universe
     }
 
-    fn get_cell_recursive(&self, node: &Rc<QuadTree>, x: i32, y: i32, node_x: i32, node_y: i32) -> bool {
-        if node.level == 0 {
-            return node.alive;
+    #[test]
+    fn set_and_get_round_trip_across_quadrants() {
+        let cells = [(0, 0), (-1, -1), (3, -4), (-4, 3), (1000, -2000), (-(1 << 40), 1 << 40)];
+        let mut universe = from_cells(&cells);
+        for &(x, y) in &cells {
+            assert!(universe.get_cell(x, y), "({}, {})", x, y);
         }
-        
-        let half_size = (node.size() / 2) as i32;
-        
-        if x < node_x {
-            if y < node_y {
-                self.get_cell_recursive(node.nw.as_ref().unwrap(), x, y, node_x - half_size, node_y - half_size)
-            } else {
-                self.get_cell_recursive(node.sw.as_ref().unwrap(), x, y, node_x - half_size, node_y + half_size)
-            }
-        } else {
-            if y < node_y {
-                self.get_cell_recursive(node.ne.as_ref().unwrap(), x, y, node_x + half_size, node_y - half_size)
-            } else {
-                self.get_cell_recursive(node.se.as_ref().unwrap(), x, y, node_x + half_size, node_y + half_size)
+        assert!(!universe.get_cell(1, 0));
+        assert!(!universe.get_cell(i64::MAX, i64::MIN));
+        assert_eq!(universe.root().population(), cells.len() as u64);
+
+        universe.set_cell(1000, -2000, false).unwrap();
+        assert!(!universe.get_cell(1000, -2000));
+        assert_eq!(universe.root().population(), cells.len() as u64 - 1);
+    }
+
+    #[test]
+    fn root_level_is_capped() {
+        let mut universe = from_cells(&[(0, 0)]);
+        assert!(universe.set_cell(i64::MAX, i64::MIN, true).is_err());
+        assert_eq!(universe.root().population(), 1);
+
+        let mut glider = from_cells(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert!(glider.step(1 << 62).is_err());
+        assert_eq!((glider.generation(), glider.root().population()), (0, 5));
+    }
+
+    #[test]
+    fn macrocell_keeps_its_rule() {
+        let universe = HashLifeUniverse::from_macrocell("[M2]\n#R B3/S23\n#G 7\n.*$..*$***$\n4 0 0 0 1\n").unwrap();
+        assert_eq!((universe.generation(), universe.root().population()), (7, 5));
+        assert!(universe.get_cell(1, 0) && universe.get_cell(2, 2));
+
+        let mut highlife = HashLifeUniverse::from_macrocell("[M2]\n#R B36/S23\n.*$..*$***$\n4 0 0 0 1\n").unwrap();
+        assert_eq!(highlife.rule(), &Rule::parse("B36/S23").unwrap());
+        highlife.clear();
+        assert_eq!((highlife.generation(), highlife.root().population()), (0, 0));
+        assert_eq!(highlife.rule(), &Rule::parse("B36/S23").unwrap());
+        assert!(HashLifeUniverse::from_macrocell("[M2]\n63 0 0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn matches_dense_universe() {
+        // 邊界外視為死亡，留足夠的空白讓逐格模擬不受邊界影響
+        const SIZE: usize = 80;
+        const GENERATIONS: u64 = 30;
+        let mut dense = Universe::new(SIZE, SIZE);
+        let mut universe = HashLifeUniverse::new();
+        let mut seed = 7u64;
+        for row in 30..50 {
+            for col in 30..50 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                if (seed >> 33) % 100 < 35 {
+                    dense.set_cell(row, col, Cell::Alive);
+                    universe.set_cell(col as i64 - 40, row as i64 - 40, true).unwrap();
+                }
             }
         }
-    }
-
-    pub fn step(&mut self) {
-        while self.root.level < 3 {
-            self.expand();
+        for _ in 0..GENERATIONS {
+            dense.tick();
         }
-        
-        let root = self.root.clone();
-        let steps = 1;
-        let new_root = self.next_generation(&root, steps);
-        self.root = new_root;
-        self.generation += steps as u64;
-    }
-
-    pub fn generation(&self) -> u64 {
-        self.generation
-    }
-
-    pub fn population(&self) -> u64 {
-        self.root.population()
-    }
+        universe.step(GENERATIONS).unwrap();
 
-    pub fn clear(&mut self) {
-        let dead_cell = QuadTree::new_cell(false);
-        self.root = QuadTree::new_node(2, dead_cell.clone(), dead_cell.clone(), dead_cell.clone(), dead_cell);
-        self.generation = 0;
-        self.cache.clear();
+        assert_eq!(universe.root().population(), dense.count_alive() as u64);
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let alive = dense.get_cell(row, col) == Cell::Alive;
+                assert_eq!(universe.get_cell(col as i64 - 40, row as i64 - 40), alive, "({}, {})", row, col);
+            }
+        }
     }
-}
\ No newline at end of file
+}
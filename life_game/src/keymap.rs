@@ -0,0 +1,136 @@
+use eframe::egui::{self, Key};
+use serde::{Deserialize, Serialize};
+
+/// 可以綁定到鍵盤的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    PlayPause,
+    Step,
+    Clear,
+    Randomize,
+    SpeedUp,
+    SpeedDown,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ToggleHelp,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::PlayPause,
+        Action::Step,
+        Action::Clear,
+        Action::Randomize,
+        Action::SpeedUp,
+        Action::SpeedDown,
+        Action::PanUp,
+        Action::PanDown,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::ToggleHelp,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::PlayPause => "開始 / 暫停",
+            Action::Step => "下一步",
+            Action::Clear => "清空",
+            Action::Randomize => "隨機",
+            Action::SpeedUp => "加速",
+            Action::SpeedDown => "減速",
+            Action::PanUp => "向上平移",
+            Action::PanDown => "向下平移",
+            Action::PanLeft => "向左平移",
+            Action::PanRight => "向右平移",
+            Action::ToggleHelp => "顯示快捷鍵",
+        }
+    }
+
+    /// 按住時每幀重複觸發的操作（平移），其餘只在按下瞬間觸發
+    pub fn repeats(self) -> bool {
+        matches!(self, Action::PanUp | Action::PanDown | Action::PanLeft | Action::PanRight)
+    }
+}
+
+/// 操作與按鍵的對應表，透過 eframe storage 持久化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    bindings: Vec<(Action, Key)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: vec![
+                (Action::PlayPause, Key::Space),
+                (Action::Step, Key::N),
+                (Action::Clear, Key::C),
+                (Action::Randomize, Key::R),
+                (Action::SpeedUp, Key::Plus),
+                (Action::SpeedDown, Key::Minus),
+                (Action::PanUp, Key::ArrowUp),
+                (Action::PanDown, Key::ArrowDown),
+                (Action::PanLeft, Key::ArrowLeft),
+                (Action::PanRight, Key::ArrowRight),
+                (Action::ToggleHelp, Key::H),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    pub const STORAGE_KEY: &'static str = "keymap";
+
+    pub fn key_for(&self, action: Action) -> Option<Key> {
+        self.bindings.iter().find(|(a, _)| *a == action).map(|(_, k)| *k)
+    }
+
+    /// 重新綁定操作；若該按鍵已被其他操作佔用則互換
+    pub fn bind(&mut self, action: Action, key: Key) {
+        let previous = self.key_for(action);
+        for (a, k) in self.bindings.iter_mut() {
+            if *k == key && *a != action {
+                match previous {
+                    Some(prev) => *k = prev,
+                    None => continue,
+                }
+            }
+        }
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, k)) => *k = key,
+            None => self.bindings.push((action, key)),
+        }
+        self.bindings.retain(|(a, k)| *a == action || *k != key);
+    }
+
+    /// 收集本幀被觸發的操作
+    pub fn triggered(&self, input: &egui::InputState) -> Vec<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(|&action| match self.key_for(action) {
+                Some(key) if action.repeats() => input.key_down(key),
+                Some(key) => input.key_pressed(key),
+                None => false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_swaps_conflicting_keys() {
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::Step, Key::Space);
+        assert_eq!(keymap.key_for(Action::Step), Some(Key::Space));
+        assert_eq!(keymap.key_for(Action::PlayPause), Some(Key::N));
+        for action in Action::ALL {
+            assert!(keymap.key_for(action).is_some());
+        }
+    }
+}
@@ -1,18 +1,32 @@
 #![windows_subsystem = "windows"]
-#![allow(private_interfaces, unused_import)]
+#![allow(private_interfaces)]
 // use std::collections::HashSet;
 use crate::egui::FontFamily;
 use eframe::{egui, NativeOptions};
 use image::load_from_memory;
-use std::path::PathBuf;
-use std::sync::Arc;
 use std::fmt;
+use std::sync::Arc;
 // use egui::FontFamily;
 use crate::egui::FontData;
 use crate::egui::FontDefinitions;
 
+mod hashlife;
+mod keymap;
+mod pattern;
+mod render;
+mod sim;
+mod theme;
+
+use hashlife::HashLifeUniverse;
+use hashlife_br::{ParallelNode, Rule};
+use keymap::{Action, Keymap};
+use pattern::Pattern;
+use render::{BoardRenderer, QuadTreeRenderer};
+use sim::{Simulation, TreeSimulation};
+use theme::{ColorMode, RenderSettings, ThemeKind};
 
-#[derive(Clone, Copy, PartialEq)]
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Cell {
     Dead = 0,
     Alive = 1,
@@ -27,36 +41,194 @@ impl fmt::Display for Cell {
     }
 }
 
+/// 調整棋盤大小時原內容對齊的位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    Center,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Anchor {
+    const ALL: [Anchor; 5] = [Anchor::TopLeft, Anchor::TopRight, Anchor::Center, Anchor::BottomLeft, Anchor::BottomRight];
+
+    fn label(self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "左上",
+            Anchor::TopRight => "右上",
+            Anchor::Center => "置中",
+            Anchor::BottomLeft => "左下",
+            Anchor::BottomRight => "右下",
+        }
+    }
+
+    /// 舊內容左上角在新棋盤中的 (列, 行) 位移
+    fn offset(self, old: (usize, usize), new: (usize, usize)) -> (isize, isize) {
+        let dy = new.0 as isize - old.0 as isize;
+        let dx = new.1 as isize - old.1 as isize;
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::TopRight => (0, dx),
+            Anchor::Center => (dy / 2, dx / 2),
+            Anchor::BottomLeft => (dy, 0),
+            Anchor::BottomRight => (dy, dx),
+        }
+    }
+}
+
+/// 以 HashLife 四叉樹顯示與推進匯入的 macrocell 或超過棋盤上限的圖樣，可以縮小到看見邊長 2^40 以上的整個圖樣
+struct QuadTreeView {
+    /// 推進與編輯都在背景執行緒進行，這裡只保留最新的根節點
+    sim: TreeSimulation,
+    root: Arc<ParallelNode>,
+    rule: Rule,
+    generation: u64,
+    error: Option<String>,
+    /// 畫面中心對應的世界座標
+    center: (f64, f64),
+    /// 每個細胞佔 2^zoom 個螢幕點
+    zoom: f64,
+    /// 下次繪製時依畫面大小縮放到能看見整個根節點
+    fit: bool,
+}
+
+impl QuadTreeView {
+    const MIN_ZOOM: f64 = -60.0;
+    const MAX_ZOOM: f64 = 5.0;
+
+    fn new(universe: HashLifeUniverse) -> Self {
+        QuadTreeView {
+            root: universe.root().clone(),
+            rule: universe.rule().clone(),
+            generation: universe.generation(),
+            error: None,
+            sim: TreeSimulation::spawn(universe),
+            center: (0.0, 0.0),
+            zoom: 0.0,
+            fit: true,
+        }
+    }
+
+    fn scale(&self) -> f64 {
+        self.zoom.exp2()
+    }
+
+    /// 畫面內容移動 `delta` 個螢幕點
+    fn pan(&mut self, delta: egui::Vec2) {
+        self.center.0 -= delta.x as f64 / self.scale();
+        self.center.1 -= delta.y as f64 / self.scale();
+    }
+
+    /// 以距畫面中心 `anchor` 的位置為定點縮放，該處的細胞維持在指標下
+    fn zoom_by(&mut self, amount: f64, anchor: egui::Vec2) {
+        let before = self.scale();
+        self.zoom = (self.zoom + amount).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let after = self.scale();
+        self.center.0 += anchor.x as f64 * (1.0 / before - 1.0 / after);
+        self.center.1 += anchor.y as f64 * (1.0 / before - 1.0 / after);
+    }
+
+    /// 距畫面中心 `offset` 處的細胞座標
+    fn cell_at(&self, offset: egui::Vec2) -> (i64, i64) {
+        (
+            (self.center.0 + offset.x as f64 / self.scale()).floor() as i64,
+            (self.center.1 + offset.y as f64 / self.scale()).floor() as i64,
+        )
+    }
+
+    /// 套用背景執行緒最新的結果
+    fn receive(&mut self) {
+        if let Some(snapshot) = self.sim.latest() {
+            self.root = snapshot.root;
+            self.generation = snapshot.generation;
+            self.error = snapshot.error;
+        }
+    }
+
+    fn fit_to(&mut self, size: egui::Vec2) {
+        let extent = (self.root.level() as f64).exp2();
+        self.zoom = (size.min_elem() as f64 / extent).log2().clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.center = (0.0, 0.0);
+        self.fit = false;
+    }
+}
+
 pub struct GameOfLifeApp {
     universe: Universe,
     is_running: bool,
-    speed: f32,
-    timer: f32,
-    grid_size: usize,
+    generations_per_second: f32,
+    /// 每一步前進 2^step_exponent 個世代
+    step_exponent: u32,
+    measured_rate: f32,
     cell_size: f32,
-    generation: u32,
-    drawing: bool,
-    erasing: bool,
+    generation: u64,
+    sim: Simulation,
+    /// UI 本幀修改過棋盤，需同步給模擬執行緒
+    dirty: bool,
+    show_board_dialog: bool,
+    board_width: usize,
+    board_height: usize,
+    anchor: Anchor,
+    show_import: bool,
+    import_text: String,
+    import_error: Option<String>,
+    keymap: Keymap,
+    pan: egui::Vec2,
+    show_help: bool,
+    rebinding: Option<Action>,
+    render: RenderSettings,
+    renderer: BoardRenderer,
+    /// 匯入 macrocell 後改以四叉樹顯示，載入一般圖樣時回到棋盤
+    quadtree: Option<QuadTreeView>,
+    quadtree_renderer: QuadTreeRenderer,
 }
 
+#[derive(Clone)]
 pub struct Universe {
     width: usize,
     height: usize,
     cells: Vec<Cell>,
+    /// 活細胞為已存活的世代數，死細胞為死亡後經過的世代數
+    ages: Vec<u16>,
 }
 
 impl Universe {
+    /// 從未存活過的死細胞的年齡
+    pub const NEVER_ALIVE: u16 = u16::MAX;
+
     pub fn new(width: usize, height: usize) -> Universe {
         let cells = vec![Cell::Dead; width * height];
+        let ages = vec![Self::NEVER_ALIVE; width * height];
         Universe {
             width,
             height,
             cells,
+            ages,
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
     pub fn clear(&mut self) {
         self.cells.fill(Cell::Dead);
+        self.ages.fill(Self::NEVER_ALIVE);
+    }
+
+    /// 手動修改細胞時重設年齡
+    fn write_cell(&mut self, idx: usize, state: Cell) {
+        self.cells[idx] = state;
+        self.ages[idx] = match state {
+            Cell::Alive => 0,
+            Cell::Dead => Self::NEVER_ALIVE,
+        };
     }
     pub fn set_gosper_glider_gun(&mut self, offset_row: usize, offset_col: usize) {
         // Gosper Glider Gun 的相對座標（經典版本）
@@ -83,7 +255,7 @@ impl Universe {
             let col = offset_col + c;
             if row < self.height && col < self.width {
                 let idx = self.get_index(row, col);
-                self.cells[idx] = Cell::Alive;
+                self.write_cell(idx, Cell::Alive);
             }
         }
     }
@@ -96,9 +268,9 @@ impl Universe {
         std::time::SystemTime::now().hash(&mut hasher);
         let seed = hasher.finish();
         
-        for (i, cell) in self.cells.iter_mut().enumerate() {
+        for i in 0..self.cells.len() {
             let x = (seed.wrapping_mul(i as u64 + 1)) % 100;
-            *cell = if x < 30 { Cell::Alive } else { Cell::Dead };
+            self.write_cell(i, if x < 30 { Cell::Alive } else { Cell::Dead });
         }
     }
 
@@ -106,7 +278,7 @@ impl Universe {
         for (row, col) in cells.iter().cloned() {
             if row < self.height && col < self.width {
                 let idx = self.get_index(row, col);
-                self.cells[idx] = Cell::Alive;
+                self.write_cell(idx, Cell::Alive);
             }
         }
     }
@@ -115,17 +287,20 @@ impl Universe {
         if row < self.height && col < self.width {
 
             let idx = self.get_index(row, col);
-            self.cells[idx] = match self.cells[idx] {
+            let state = match self.cells[idx] {
                 Cell::Alive => Cell::Dead,
                 Cell::Dead => Cell::Alive,
             };
+            self.write_cell(idx, state);
         }
     }
 
     pub fn set_cell(&mut self, row: usize, col: usize, state: Cell) {
         if row < self.height && col < self.width {
             let idx = self.get_index(row, col);
-            self.cells[idx] = state;
+            if self.cells[idx] != state {
+                self.write_cell(idx, state);
+            }
         }
     }
 
@@ -138,6 +313,16 @@ impl Universe {
         }
     }
 
+    /// 活細胞回傳已存活的世代數（剛誕生為 0），死細胞回傳死亡後經過的世代數；
+    /// 從未存活過的死細胞為 `NEVER_ALIVE`
+    pub fn get_age(&self, row: usize, col: usize) -> u16 {
+        if row < self.height && col < self.width {
+            self.ages[self.get_index(row, col)]
+        } else {
+            Self::NEVER_ALIVE
+        }
+    }
+
     fn get_index(&self, row: usize, col: usize) -> usize {
         row * self.width + col
     }
@@ -182,6 +367,11 @@ impl Universe {
                 };
 
                 next[idx] = next_cell;
+                self.ages[idx] = if next_cell == cell {
+                    self.ages[idx].saturating_add(1)
+                } else {
+                    0
+                };
             }
         }
 
@@ -191,6 +381,30 @@ impl Universe {
     pub fn count_alive(&self) -> usize {
         self.cells.iter().filter(|&&cell| cell == Cell::Alive).count()
     }
+
+    /// 調整棋盤大小並保留內容，超出新範圍的細胞會被裁掉
+    pub fn resize(&mut self, width: usize, height: usize, anchor: Anchor) {
+        let (dy, dx) = anchor.offset((self.height, self.width), (height, width));
+
+        let mut resized = Universe::new(width, height);
+        for row in 0..self.height {
+            let new_row = row as isize + dy;
+            if new_row < 0 || new_row >= height as isize {
+                continue;
+            }
+            for col in 0..self.width {
+                let new_col = col as isize + dx;
+                if new_col < 0 || new_col >= width as isize {
+                    continue;
+                }
+                let from = self.get_index(row, col);
+                let to = resized.get_index(new_row as usize, new_col as usize);
+                resized.cells[to] = self.cells[from];
+                resized.ages[to] = self.ages[from];
+            }
+        }
+        *self = resized;
+    }
 }
 
 impl Default for GameOfLifeApp {
@@ -209,21 +423,328 @@ impl Default for GameOfLifeApp {
         ]);
 
         Self {
+            sim: Simulation::spawn(universe.clone()),
             universe,
             is_running: false,
-            speed: 10.0,
-            timer: 0.0,
-            grid_size,
+            generations_per_second: 10.0,
+            step_exponent: 0,
+            measured_rate: 0.0,
             cell_size: 8.0,
             generation: 0,
-            drawing: false,
-            erasing: false,
+            dirty: false,
+            show_board_dialog: false,
+            board_width: grid_size,
+            board_height: grid_size,
+            anchor: Anchor::Center,
+            show_import: false,
+            import_text: String::new(),
+            import_error: None,
+            keymap: Keymap::default(),
+            pan: egui::Vec2::ZERO,
+            show_help: false,
+            rebinding: None,
+            render: RenderSettings::default(),
+            renderer: BoardRenderer::default(),
+            quadtree: None,
+            quadtree_renderer: QuadTreeRenderer::default(),
+        }
+    }
+}
+
+impl GameOfLifeApp {
+    /// 平移速度（像素 / 秒）
+    const PAN_SPEED: f32 = 400.0;
+
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            if let Some(keymap) = eframe::get_value(storage, Keymap::STORAGE_KEY) {
+                app.keymap = keymap;
+            }
+            if let Some(render) = eframe::get_value(storage, RenderSettings::STORAGE_KEY) {
+                app.render = render;
+            }
+        }
+        app
+    }
+
+    /// 每秒世代數的上限
+    const MAX_RATE: f32 = 100_000.0;
+    /// 棋盤邊長的上限，調整大小與匯入圖樣共用
+    const MAX_BOARD_SIZE: usize = 4096;
+
+    fn set_running(&mut self, running: bool) {
+        self.sync_edits();
+        self.is_running = running;
+        self.sim.set_running(running);
+    }
+
+    /// 先送出本幀的編輯，模擬執行緒才會從編輯後的棋盤前進
+    fn step(&mut self) {
+        self.sync_edits();
+        self.sim.step();
+    }
+
+    fn clear(&mut self) {
+        self.universe.clear();
+        self.generation = 0;
+        self.dirty = true;
+    }
+
+    fn randomize(&mut self) {
+        self.universe.randomize();
+        self.generation = 0;
+        self.dirty = true;
+    }
+
+    /// 清空棋盤後放置圖樣
+    fn load_pattern(&mut self, place: impl FnOnce(&mut Universe)) {
+        self.quadtree = None;
+        self.universe.clear();
+        place(&mut self.universe);
+        self.generation = 0;
+        self.dirty = true;
+    }
+
+    /// 匯入圖樣會清空棋盤並置中放置；放不下時自動擴大棋盤。超過棋盤上限，或規則不是
+    /// 棋盤所用的康威生命遊戲時，改以大尺度檢視開啟
+    fn import_pattern(&mut self, pattern: &Pattern) -> Result<(), String> {
+        const MARGIN: usize = 10;
+        let width = self.universe.width().max(pattern.width.saturating_add(2 * MARGIN));
+        let height = self.universe.height().max(pattern.height.saturating_add(2 * MARGIN));
+        if width > Self::MAX_BOARD_SIZE || height > Self::MAX_BOARD_SIZE || pattern.rule != Rule::default() {
+            let mut tree = HashLifeUniverse::with_rule(pattern.rule.clone());
+            let center = |len: usize| (len / 2) as i64;
+            for &(row, col) in &pattern.cells {
+                tree.set_cell(col as i64 - center(pattern.width), row as i64 - center(pattern.height), true)?;
+            }
+            self.set_running(false);
+            self.quadtree = Some(QuadTreeView::new(tree));
+            return Ok(());
+        }
+        if width != self.universe.width() || height != self.universe.height() {
+            self.universe = Universe::new(width, height);
+            self.board_width = width;
+            self.board_height = height;
+        }
+        let offset_row = (height - pattern.height) / 2;
+        let offset_col = (width - pattern.width) / 2;
+        let cells: Vec<_> = pattern
+            .cells
+            .iter()
+            .map(|&(row, col)| (row + offset_row, col + offset_col))
+            .collect();
+        self.load_pattern(|u| u.set_cells(&cells));
+        Ok(())
+    }
+
+    fn show_board_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_board_dialog;
+        let mut apply = false;
+        egui::Window::new("📐 棋盤大小")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("board_size_grid").show(ui, |ui| {
+                    ui.label("寬:");
+                    ui.add(egui::DragValue::new(&mut self.board_width).range(1..=Self::MAX_BOARD_SIZE));
+                    ui.end_row();
+                    ui.label("高:");
+                    ui.add(egui::DragValue::new(&mut self.board_height).range(1..=Self::MAX_BOARD_SIZE));
+                    ui.end_row();
+                });
+                ui.label("保留內容的位置:");
+                ui.horizontal_wrapped(|ui| {
+                    for anchor in Anchor::ALL {
+                        ui.selectable_value(&mut self.anchor, anchor, anchor.label());
+                    }
+                });
+                ui.separator();
+                apply = ui.button("套用").clicked();
+            });
+        if apply {
+            self.universe.resize(self.board_width, self.board_height, self.anchor);
+            self.dirty = true;
+            open = false;
+        }
+        self.show_board_dialog = open;
+    }
+
+    fn show_import_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_import;
+        let mut imported = None;
+        let mut imported_tree = None;
+        egui::Window::new("📥 匯入圖樣")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("貼上 RLE、純文字 (.cells) 或 macrocell (.mc) 圖樣:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.import_text)
+                        .code_editor()
+                        .desired_rows(10),
+                );
+                if let Some(error) = &self.import_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 80, 80), error);
+                }
+                if ui.button("匯入").clicked() {
+                    if self.import_text.trim_start().starts_with("[M2]") {
+                        match HashLifeUniverse::from_macrocell(&self.import_text) {
+                            Ok(universe) => imported_tree = Some(universe),
+                            Err(error) => self.import_error = Some(error),
+                        }
+                    } else {
+                        match Pattern::parse(&self.import_text) {
+                            Ok(pattern) => imported = Some(pattern),
+                            Err(error) => self.import_error = Some(error),
+                        }
+                    }
+                }
+            });
+        if let Some(pattern) = imported {
+            match self.import_pattern(&pattern) {
+                Ok(()) => {
+                    self.import_error = None;
+                    open = false;
+                }
+                Err(error) => self.import_error = Some(error),
+            }
+        }
+        if let Some(universe) = imported_tree {
+            self.set_running(false);
+            self.quadtree = Some(QuadTreeView::new(universe));
+            self.import_error = None;
+            open = false;
+        }
+        self.show_import = open;
+    }
+
+    fn set_rate(&mut self, generations_per_second: f32) {
+        self.generations_per_second = generations_per_second.clamp(1.0, Self::MAX_RATE);
+        self.sim.configure(self.generations_per_second, self.step_exponent);
+    }
+
+    /// 套用模擬執行緒最新的快照
+    fn receive_snapshot(&mut self) {
+        if let Some(snapshot) = self.sim.latest() {
+            self.universe = snapshot.universe;
+            self.generation = snapshot.generation;
+            self.measured_rate = snapshot.measured_rate;
+        }
+    }
+
+    /// 把本幀的編輯同步給模擬執行緒
+    fn sync_edits(&mut self) {
+        if self.dirty {
+            self.sim.load(self.universe.clone(), self.generation);
+            self.dirty = false;
+        }
+    }
+
+    fn perform(&mut self, action: Action, dt: f32) {
+        let pan = Self::PAN_SPEED * dt;
+        if let Some(view) = &mut self.quadtree {
+            match action {
+                Action::Step => view.sim.step(1 << self.step_exponent),
+                Action::Clear => view.sim.clear(),
+                Action::PanUp => view.pan(egui::vec2(0.0, pan)),
+                Action::PanDown => view.pan(egui::vec2(0.0, -pan)),
+                Action::PanLeft => view.pan(egui::vec2(pan, 0.0)),
+                Action::PanRight => view.pan(egui::vec2(-pan, 0.0)),
+                Action::ToggleHelp => self.show_help = !self.show_help,
+                _ => {}
+            }
+            return;
+        }
+        match action {
+            Action::PlayPause => self.set_running(!self.is_running),
+            Action::Step => self.step(),
+            Action::Clear => self.clear(),
+            Action::Randomize => self.randomize(),
+            Action::SpeedUp => self.set_rate(self.generations_per_second * 2.0),
+            Action::SpeedDown => self.set_rate(self.generations_per_second / 2.0),
+            Action::PanUp => self.pan.y += pan,
+            Action::PanDown => self.pan.y -= pan,
+            Action::PanLeft => self.pan.x += pan,
+            Action::PanRight => self.pan.x -= pan,
+            Action::ToggleHelp => self.show_help = !self.show_help,
+        }
+    }
+
+    /// 處理鍵盤快捷鍵；重新綁定時攔截下一個按鍵
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if let Some(action) = self.rebinding {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, .. } => Some(*key),
+                    _ => None,
+                })
+            });
+            match pressed {
+                Some(egui::Key::Escape) => self.rebinding = None,
+                Some(key) => {
+                    self.keymap.bind(action, key);
+                    self.rebinding = None;
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (actions, dt) = ctx.input(|i| (self.keymap.triggered(i), i.unstable_dt));
+        for action in actions {
+            self.perform(action, dt);
+        }
+    }
+
+    fn show_help_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_help;
+        egui::Window::new("⌨ 快捷鍵")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("keymap_grid").striped(true).show(ui, |ui| {
+                    for action in Action::ALL {
+                        ui.label(action.label());
+                        let text = if self.rebinding == Some(action) {
+                            "按下新按鍵…".to_owned()
+                        } else {
+                            self.keymap
+                                .key_for(action)
+                                .map_or("未綁定", |key| key.name())
+                                .to_owned()
+                        };
+                        if ui.button(text).clicked() {
+                            self.rebinding = Some(action);
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                if ui.button("恢復預設").clicked() {
+                    self.keymap = Keymap::default();
+                    self.rebinding = None;
+                }
+            });
+        self.show_help = open;
+        if !open {
+            self.rebinding = None;
         }
     }
 }
 
 impl eframe::App for GameOfLifeApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.receive_snapshot();
+        if let Some(view) = &mut self.quadtree {
+            view.receive();
+        }
+
         // 頂部控制面板
         let mut fonts: FontDefinitions = FontDefinitions::default();
 
@@ -253,44 +774,110 @@ impl eframe::App for GameOfLifeApp {
         });
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.button(if self.is_running { "⏸ 暫停" } else { "▶ 開始" }).clicked() {
-                    self.is_running = !self.is_running;
-                }
+                if let Some(view) = &mut self.quadtree {
+                    ui.label("🔭 大尺度檢視");
+                    if ui.button(format!("⏭ 前進 {}", 1u64 << self.step_exponent)).clicked() {
+                        view.sim.step(1 << self.step_exponent);
+                    }
+                    if view.sim.busy() {
+                        ui.spinner();
+                    }
+                    ui.label("步長:");
+                    let step = ui.add(
+                        egui::Slider::new(&mut self.step_exponent, 0..=16)
+                            .custom_formatter(|n, _| format!("{}", 1u64 << n as u32)),
+                    );
+                    // 步長與棋盤模式共用，回到棋盤時模擬執行緒也要用新的步長
+                    if step.changed() {
+                        self.sim.configure(self.generations_per_second, self.step_exponent);
+                    }
+                    ui.separator();
+                    ui.label("縮放:");
+                    ui.add(
+                        egui::Slider::new(&mut view.zoom, QuadTreeView::MIN_ZOOM..=QuadTreeView::MAX_ZOOM)
+                            .custom_formatter(|zoom, _| format!("2^{:.1}", zoom)),
+                    );
+                    if ui.button("全覽").clicked() {
+                        view.fit = true;
+                    }
+                    ui.separator();
+                    ui.label(format!("世代: {}", view.generation));
+                    ui.label(format!("活細胞: {}", view.root.population()));
+                    ui.label(format!("範圍: 2^{}", view.root.level()));
+                    ui.label(format!("規則: {}", view.rule));
+                    if let Some(error) = &view.error {
+                        ui.colored_label(egui::Color32::from_rgb(255, 80, 80), error);
+                    }
+                    if ui.button("返回棋盤").clicked() {
+                        self.quadtree = None;
+                    }
+                } else {
+                    if ui.button(if self.is_running { "⏸ 暫停" } else { "▶ 開始" }).clicked() {
+                        self.set_running(!self.is_running);
+                    }
                 
-                if ui.button("⏹ 停止").clicked() {
-                    self.is_running = false;
-                    self.generation = 0;
-                }
+                    if ui.button("⏹ 停止").clicked() {
+                        self.set_running(false);
+                        self.generation = 0;
+                        self.dirty = true;
+                    }
                 
-                if ui.button("⏭ 下一步").clicked() {
-                    self.universe.tick();
-                    self.generation += 1;
-                }
+                    if ui.button("⏭ 下一步").clicked() {
+                        self.step();
+                    }
                 
-                if ui.button("🗑 清空").clicked() {
-                    self.universe.clear();
-                    self.generation = 0;
-                }
+                    if ui.button("🗑 清空").clicked() {
+                        self.clear();
+                    }
                 
-                if ui.button("🎲 隨機").clicked() {
-                    self.universe.randomize();
-                    self.generation = 0;
-                }
+                    if ui.button("🎲 隨機").clicked() {
+                        self.randomize();
+                    }
                 
-                ui.separator();
+                    ui.separator();
                 
-                ui.label("速度:");
-                ui.add(egui::Slider::new(&mut self.speed, 1.0..=256.0).suffix(" FPS"));
+                    ui.label("速度:");
+                    let rate = ui.add(
+                        egui::Slider::new(&mut self.generations_per_second, 1.0..=Self::MAX_RATE)
+                            .logarithmic(true)
+                            .suffix(" 世代/秒"),
+                    );
+                    ui.label("步長:");
+                    let step = ui.add(
+                        egui::Slider::new(&mut self.step_exponent, 0..=16)
+                            .custom_formatter(|n, _| format!("{}", 1u64 << n as u32)),
+                    );
+                    if rate.changed() || step.changed() {
+                        self.set_rate(self.generations_per_second);
+                    }
                 
-                ui.separator();
+                    ui.separator();
                 
-                ui.label("細胞大小:");
-                ui.add(egui::Slider::new(&mut self.cell_size, 2.0..=20.0));
+                    ui.label("細胞大小:");
+                    ui.add(egui::Slider::new(&mut self.cell_size, 0.05..=20.0).logarithmic(true));
                 
-                ui.separator();
+                    ui.separator();
                 
-                ui.label(format!("世代: {}", self.generation));
-                ui.label(format!("活細胞: {}", self.universe.count_alive()));
+                    ui.label(format!("世代: {}", self.generation));
+                    if self.is_running {
+                        ui.label(format!("實際: {:.0} 世代/秒", self.measured_rate));
+                    }
+                    ui.label(format!("活細胞: {}", self.universe.count_alive()));
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("⌨ 快捷鍵").clicked() {
+                        self.show_help = !self.show_help;
+                    }
+                    if ui.button("📥 匯入").clicked() {
+                        self.show_import = !self.show_import;
+                    }
+                    if ui.button(format!("📐 {}x{}", self.universe.width(), self.universe.height())).clicked() {
+                        self.board_width = self.universe.width();
+                        self.board_height = self.universe.height();
+                        self.show_board_dialog = !self.show_board_dialog;
+                    }
+                });
             });
         });
 
@@ -299,54 +886,64 @@ impl eframe::App for GameOfLifeApp {
             ui.heading("經典模式");
             
             if ui.button("滑翔機").clicked() {
-                self.universe.clear();
-                self.universe.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
-                self.generation = 0;
+                self.load_pattern(|u| u.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]));
             }
             
             if ui.button("振盪器").clicked() {
-                self.universe.clear();
-                self.universe.set_cells(&[(10, 10), (10, 11), (10, 12)]);
-                self.generation = 0;
+                self.load_pattern(|u| u.set_cells(&[(10, 10), (10, 11), (10, 12)]));
             }
             
             if ui.button("蟾蜍").clicked() {
-                self.universe.clear();
-                self.universe.set_cells(&[
+                self.load_pattern(|u| u.set_cells(&[
                     (10, 11), (10, 12), (10, 13),
                     (11, 10), (11, 11), (11, 12)
-                ]);
-                self.generation = 0;
+                ]));
             }
             if ui.button("滑翔機槍").clicked() {
-                self.universe.clear();
-                self.universe.set_gosper_glider_gun(0, 0);
-                self.generation = 0;
-           }
+                self.load_pattern(|u| u.set_gosper_glider_gun(0, 0));
+            }
 
             if ui.button("信標").clicked() {
-                self.universe.clear();
-                self.universe.set_cells(&[
+                self.load_pattern(|u| u.set_cells(&[
                     (10, 10), (10, 11), (11, 10), (11, 11),
                     (12, 12), (12, 13), (13, 12), (13, 13)
-                ]);
-                self.generation = 0;
+                ]));
             }
             
             if ui.button("太空船").clicked() {
-                self.universe.clear();
-                self.universe.set_cells(&[
+                self.load_pattern(|u| u.set_cells(&[
                     (10, 11), (10, 14), (11, 15), (12, 11), (12, 15),
                     (13, 12), (13, 13), (13, 14), (13, 15)
-                ]);
-                self.generation = 0;
+                ]));
             }
             
+            ui.separator();
+            ui.heading("外觀");
+
+            egui::ComboBox::from_label("主題")
+                .selected_text(self.render.theme.label())
+                .show_ui(ui, |ui| {
+                    for kind in ThemeKind::ALL {
+                        ui.selectable_value(&mut self.render.theme, kind, kind.label());
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.render.color_mode, ColorMode::State, "生死");
+                ui.selectable_value(&mut self.render.color_mode, ColorMode::Age, "年齡");
+            });
+            ui.checkbox(&mut self.render.show_grid, "顯示網格");
+            ui.add(
+                egui::Slider::new(&mut self.render.grid_min_cell_size, 2.0..=20.0)
+                    .text("網格最小細胞"),
+            );
+
             ui.separator();
             ui.label("使用說明:");
             ui.label("• 點擊細胞切換狀態");
             ui.label("• 拖拽繪製細胞");
             ui.label("• 按住 Shift 擦除");
+            ui.label("• 方向鍵平移畫面");
+            ui.label("• 按 H 查看快捷鍵");
         });
 
         // 主要遊戲區域
@@ -354,66 +951,84 @@ impl eframe::App for GameOfLifeApp {
             let available_rect = ui.available_rect_before_wrap();
             let (response, painter) = ui.allocate_painter(available_rect.size(), egui::Sense::click_and_drag());
             
-            // 處理鼠標輸入
-            if response.drag_started() || response.dragged() {
-                if let Some(pointer_pos) = response.interact_pointer_pos() {
-                    let relative_pos = pointer_pos - response.rect.min;
-                    let col = (relative_pos.x / self.cell_size) as usize;
-                    let row = (relative_pos.y / self.cell_size) as usize;
-                    
-                    if response.drag_started() {
-                        self.universe.toggle_cell(row, col);
-                    } else if response.dragged() {
-                        let is_shift_held = ui.input(|i| i.modifiers.shift);
-                        let state = if is_shift_held { Cell::Dead } else { Cell::Alive };
-                        self.universe.set_cell(row, col, state);
+            if let Some(view) = &mut self.quadtree {
+                if view.fit {
+                    view.fit_to(response.rect.size());
+                }
+                if response.dragged() {
+                    view.pan(response.drag_delta());
+                }
+                // 細胞小到看不清時點擊沒有意義，只在放大後允許編輯
+                if response.clicked() && view.scale() >= 2.0 {
+                    if let Some(pointer) = response.interact_pointer_pos() {
+                        let (x, y) = view.cell_at(pointer - response.rect.center());
+                        view.sim.toggle(x, y);
                     }
                 }
-            }
-            
-            // 繪製網格
-            let grid_color = egui::Color32::from_gray(100);
-            let alive_color = egui::Color32::from_rgb(0, 255, 0);
-            let dead_color = egui::Color32::from_rgb(20, 20, 20);
-            
-            // 繪製細胞
-            for row in 0..self.universe.height {
-                for col in 0..self.universe.width {
-                    let cell = self.universe.get_cell(row, col);
-                    let x = response.rect.min.x + col as f32 * self.cell_size;
-                    let y = response.rect.min.y + row as f32 * self.cell_size;
-                    
-                    let rect = egui::Rect::from_min_size(
-                        egui::pos2(x, y),
-                        egui::vec2(self.cell_size, self.cell_size)
-                    );
-                    
-                    let color = match cell {
-                        Cell::Alive => alive_color,
-                        Cell::Dead => dead_color,
-                    };
-                    
-                    painter.rect_filled(rect, 0.0, color);
-                    painter.rect_stroke(rect, 0.0, egui::Stroke::new(0.5, grid_color));
+                if let Some(pointer) = response.hover_pos() {
+                    let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                    if scroll != 0.0 {
+                        view.zoom_by(scroll as f64 / 200.0, pointer - response.rect.center());
+                    }
                 }
-            }
-        });
+                self.quadtree_renderer.paint(
+                    &painter,
+                    response.rect,
+                    &view.root,
+                    view.center,
+                    view.scale(),
+                    &self.render,
+                );
+            } else {
+                // 處理鼠標輸入
+                if response.drag_started() || response.dragged() {
+                    if let Some(pointer_pos) = response.interact_pointer_pos() {
+                        let relative_pos = pointer_pos - response.rect.min - self.pan;
+                        // 平移後指標可能落在棋盤左上方，負數轉 usize 會被截成 0
+                        if relative_pos.x >= 0.0 && relative_pos.y >= 0.0 {
+                            let col = (relative_pos.x / self.cell_size) as usize;
+                            let row = (relative_pos.y / self.cell_size) as usize;
 
-        // 自動更新邏輯
-        if self.is_running {
-            self.timer += ctx.input(|i: &egui::InputState| i.unstable_dt);
-            let target_interval = 1.0 / self.speed;
+                            if response.drag_started() {
+                                self.universe.toggle_cell(row, col);
+                                self.dirty = true;
+                            } else if response.dragged() {
+                                let is_shift_held = ui.input(|i| i.modifiers.shift);
+                                let state = if is_shift_held { Cell::Dead } else { Cell::Alive };
+                                self.universe.set_cell(row, col, state);
+                                self.dirty = true;
+                            }
+                        }
+                    }
+                }
             
-            if self.timer >= target_interval {
-                self.universe.tick();
-                self.generation += 1;
-                self.timer = 0.0;
+                // 繪製細胞
+                self.renderer.paint(
+                    &painter,
+                    response.rect.min + self.pan,
+                    &self.universe,
+                    self.cell_size,
+                    &self.render,
+                );
             }
-        }
+        });
+
+        self.show_help_window(ctx);
+        self.show_board_dialog(ctx);
+        self.show_import_dialog(ctx);
+        self.handle_shortcuts(ctx);
+
+        // 世代推進在模擬執行緒進行，這裡只同步編輯
+        self.sync_edits();
         
         // 持續重繪
         ctx.request_repaint();
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Keymap::STORAGE_KEY, &self.keymap);
+        eframe::set_value(storage, RenderSettings::STORAGE_KEY, &self.render);
+    }
 }
 
 
@@ -459,6 +1074,42 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "康威生命遊戲",
         options,
-        Box::new(|_cc| Ok(Box::new(GameOfLifeApp::default()))),
+        Box::new(|cc| Ok(Box::new(GameOfLifeApp::new(cc)))),
     )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{Anchor, Cell, Universe};
+
+    #[test]
+    fn blinker_tracks_cell_ages() {
+        let mut universe = Universe::new(5, 5);
+        universe.set_cells(&[(2, 1), (2, 2), (2, 3)]);
+        universe.tick();
+        // 中心細胞持續存活，兩端剛死亡，上下剛誕生
+        assert_eq!(universe.get_age(2, 2), 1);
+        assert_eq!(universe.get_cell(2, 1), Cell::Dead);
+        assert_eq!(universe.get_age(2, 1), 0);
+        assert_eq!(universe.get_age(1, 2), 0);
+        assert_eq!(universe.get_age(0, 0), Universe::NEVER_ALIVE);
+        universe.tick();
+        assert_eq!(universe.get_age(2, 2), 2);
+        assert_eq!(universe.get_age(2, 1), 0);
+        assert_eq!(universe.get_age(1, 2), 0);
+    }
+
+    #[test]
+    fn resize_preserves_cells_at_anchor() {
+        let mut universe = Universe::new(4, 4);
+        universe.set_cells(&[(0, 0), (3, 3)]);
+
+        let mut centered = universe.clone();
+        centered.resize(6, 6, Anchor::Center);
+        assert_eq!(centered.get_cell(1, 1), Cell::Alive);
+        assert_eq!(centered.get_cell(4, 4), Cell::Alive);
+
+        universe.resize(2, 2, Anchor::BottomRight);
+        assert_eq!(universe.count_alive(), 1);
+        assert_eq!(universe.get_cell(1, 1), Cell::Alive);
+    }
+}
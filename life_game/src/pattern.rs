@@ -0,0 +1,100 @@
+use hashlife_br::{RlePattern, Rule};
+
+/// 以 (row, col) 表示的活細胞座標，左上角為原點
+pub struct Pattern {
+    pub cells: Vec<(usize, usize)>,
+    pub width: usize,
+    pub height: usize,
+    /// RLE 標頭指定的規則，純文字格式一律為康威生命遊戲
+    pub rule: Rule,
+}
+
+impl Pattern {
+    /// 解析 RLE 格式；也接受以 `.`/`O` 表示的純文字格式（.cells）
+    pub fn parse(text: &str) -> Result<Pattern, String> {
+        // 純文字格式中的空行是一整列死細胞，只去掉頭尾的空行
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#') && !line.starts_with('!'))
+            .collect();
+        let (Some(first), Some(last)) = (
+            lines.iter().position(|line| !line.is_empty()),
+            lines.iter().rposition(|line| !line.is_empty()),
+        ) else {
+            return Err("沒有任何圖樣內容".to_owned());
+        };
+        let body = &lines[first..=last];
+        if !body[0].starts_with('x') && body.iter().all(|line| line.chars().all(|c| matches!(c, '.' | 'O' | '*'))) {
+            Ok(Self::parse_plaintext(body))
+        } else {
+            Self::parse_rle(text)
+        }
+    }
+
+    fn parse_plaintext(lines: &[&str]) -> Pattern {
+        let cells = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c != '.')
+                    .map(move |(col, _)| (row, col))
+            })
+            .collect();
+        Self::from_cells(cells)
+    }
+
+    /// RLE 交給 hashlife_br 解析，包含標頭中的規則與過大圖樣的檢查
+    fn parse_rle(text: &str) -> Result<Pattern, String> {
+        let pattern = RlePattern::parse(text).map_err(|e| format!("RLE 格式錯誤: {}", e))?;
+        let cells = pattern.cells.iter().map(|&(x, y)| (y as usize, x as usize)).collect();
+        Ok(Pattern { rule: pattern.rule, ..Self::from_cells(cells) })
+    }
+
+    fn from_cells(cells: Vec<(usize, usize)>) -> Pattern {
+        let width = cells.iter().map(|&(_, col)| col + 1).max().unwrap_or(0);
+        let height = cells.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+        Pattern { cells, width, height, rule: Rule::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    #[test]
+    fn parses_rle_glider() {
+        let pattern = Pattern::parse("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+        assert_eq!(pattern.cells, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+    }
+
+    #[test]
+    fn parses_plaintext_blinker() {
+        let pattern = Pattern::parse("!Name: Blinker\n...\nOOO\n").unwrap();
+        assert_eq!(pattern.cells, vec![(1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn rle_keeps_its_rule_and_rejects_huge_runs() {
+        let pattern = Pattern::parse("x = 3, y = 1, rule = B36/S23\n3o!").unwrap();
+        assert_eq!(pattern.rule.to_string(), "B36/S23");
+        assert!(Pattern::parse("4000000000o!").is_err());
+        assert!(Pattern::parse("18446744073709551615b18446744073709551615bo!").is_err());
+    }
+
+    #[test]
+    fn plaintext_keeps_blank_rows() {
+        let pattern = Pattern::parse("!Name: Gap
+
+O
+
+.O
+
+").unwrap();
+        assert_eq!(pattern.cells, vec![(0, 0), (2, 1)]);
+        assert_eq!(pattern.height, 3);
+    }
+}
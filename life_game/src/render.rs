@@ -0,0 +1,324 @@
+use eframe::egui::{self, Color32, ColorImage, Pos2, Rect, TextureHandle, TextureOptions};
+
+use hashlife_br::ParallelNode;
+use crate::theme::{RenderSettings, Theme};
+use crate::{Cell, Universe};
+
+/// 把棋盤光柵化成單一材質再上傳，避免每個細胞各自產生一個 shape
+#[derive(Default)]
+pub struct BoardRenderer {
+    texture: Option<TextureHandle>,
+}
+
+impl BoardRenderer {
+    /// 繪製 `universe` 在 painter 裁切範圍內可見的部分；`origin` 為棋盤左上角在螢幕上的位置
+    pub fn paint(
+        &mut self,
+        painter: &egui::Painter,
+        origin: Pos2,
+        universe: &Universe,
+        cell_size: f32,
+        settings: &RenderSettings,
+    ) {
+        let ctx = painter.ctx();
+        let clip = painter.clip_rect();
+        let visible_range = |min: f32, max: f32, len: usize| {
+            let first = ((min / cell_size).floor().max(0.0) as usize).min(len);
+            let last = ((max / cell_size).ceil().max(0.0) as usize).min(len);
+            first..last
+        };
+        let cols = visible_range(clip.min.x - origin.x, clip.max.x - origin.x, universe.width());
+        let rows = visible_range(clip.min.y - origin.y, clip.max.y - origin.y, universe.height());
+        if cols.is_empty() || rows.is_empty() {
+            return;
+        }
+
+        // 細胞小於一個實體像素時，把 block x block 個細胞合併成一個材質像素
+        let physical_cell = cell_size * ctx.pixels_per_point();
+        let block = if physical_cell < 1.0 {
+            (1.0 / physical_cell).ceil() as usize
+        } else {
+            1
+        };
+        let tex_w = cols.len().div_ceil(block);
+        let tex_h = rows.len().div_ceil(block);
+
+        let theme = settings.theme.theme();
+        let mut pixels = Vec::with_capacity(tex_w * tex_h);
+        for ty in 0..tex_h {
+            let row_start = rows.start + ty * block;
+            let row_end = (row_start + block).min(rows.end);
+            for tx in 0..tex_w {
+                let col_start = cols.start + tx * block;
+                let col_end = (col_start + block).min(cols.end);
+                let color = if block == 1 {
+                    let alive = universe.get_cell(row_start, col_start) == Cell::Alive;
+                    settings.cell_color(&theme, alive, universe.get_age(row_start, col_start))
+                } else {
+                    let mut sum = [0u32; 3];
+                    for row in row_start..row_end {
+                        for col in col_start..col_end {
+                            let alive = universe.get_cell(row, col) == Cell::Alive;
+                            let c = settings.cell_color(&theme, alive, universe.get_age(row, col));
+                            sum[0] += c.r() as u32;
+                            sum[1] += c.g() as u32;
+                            sum[2] += c.b() as u32;
+                        }
+                    }
+                    let n = ((row_end - row_start) * (col_end - col_start)) as u32;
+                    Color32::from_rgb((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8)
+                };
+                pixels.push(color);
+            }
+        }
+
+        let image = ColorImage {
+            size: [tex_w, tex_h],
+            pixels,
+        };
+        let texture = upload(&mut self.texture, ctx, "board", image);
+
+        let region = Rect::from_min_max(
+            origin + egui::vec2(cols.start as f32, rows.start as f32) * cell_size,
+            origin + egui::vec2(cols.end as f32, rows.end as f32) * cell_size,
+        );
+        // 最後一列材質像素可能只有部分對應到細胞
+        let uv = Rect::from_min_max(
+            Pos2::ZERO,
+            egui::pos2(
+                cols.len() as f32 / (tex_w * block) as f32,
+                rows.len() as f32 / (tex_h * block) as f32,
+            ),
+        );
+        painter.image(texture.id(), region, uv, Color32::WHITE);
+
+        if settings.grid_visible(cell_size) {
+            let stroke = egui::Stroke::new(0.5, theme.grid);
+            for col in cols.start..=cols.end {
+                let x = origin.x + col as f32 * cell_size;
+                painter.line_segment([egui::pos2(x, region.min.y), egui::pos2(x, region.max.y)], stroke);
+            }
+            for row in rows.start..=rows.end {
+                let y = origin.y + row as f32 * cell_size;
+                painter.line_segment([egui::pos2(region.min.x, y), egui::pos2(region.max.x, y)], stroke);
+            }
+        }
+    }
+}
+
+/// 以四叉樹繪製任意縮放倍率的圖樣：只往下走到與一個材質像素同大的節點，
+/// 部分有活細胞的節點依活細胞比例畫成介於死活之間的顏色
+#[derive(Default)]
+pub struct QuadTreeRenderer {
+    texture: Option<TextureHandle>,
+}
+
+impl QuadTreeRenderer {
+    /// 在 `rect` 內繪製以原點為中心的 `root`；`center` 為 `rect` 中心對應的世界座標，
+    /// `scale` 為每個細胞佔的螢幕點數，可以遠小於一個像素
+    pub fn paint(
+        &mut self,
+        painter: &egui::Painter,
+        rect: Rect,
+        root: &ParallelNode,
+        center: (f64, f64),
+        scale: f64,
+        settings: &RenderSettings,
+    ) {
+        let ctx = painter.ctx();
+        let view = rect.intersect(painter.clip_rect());
+        if !view.is_positive() {
+            return;
+        }
+        let to_world = |p: Pos2| {
+            (
+                center.0 + (p.x - rect.center().x) as f64 / scale,
+                center.1 + (p.y - rect.center().y) as f64 / scale,
+            )
+        };
+        let to_screen = |x: f64, y: f64| {
+            egui::pos2(
+                rect.center().x + ((x - center.0) * scale) as f32,
+                rect.center().y + ((y - center.1) * scale) as f32,
+            )
+        };
+
+        // 材質像素邊長取 2^level 個細胞，level 為不小於一個實體像素的最小值，
+        // 這樣每個 level 相同的節點剛好對齊一個材質像素
+        let physical_scale = scale * ctx.pixels_per_point() as f64;
+        let level = if physical_scale >= 1.0 {
+            0
+        } else {
+            ((1.0 / physical_scale).log2().ceil() as u32).min(hashlife_br::Universe::MAX_LEVEL)
+        };
+        let texel = (1i64 << level) as f64;
+        let half = (1i64 << root.level() >> 1) as f64;
+        let (min_x, min_y) = to_world(view.min);
+        let (max_x, max_y) = to_world(view.max);
+        let (tx0, tx1) = (
+            (min_x.max(-half) / texel).floor() as i64,
+            (max_x.min(half) / texel).ceil() as i64,
+        );
+        let (ty0, ty1) = (
+            (min_y.max(-half) / texel).floor() as i64,
+            (max_y.min(half) / texel).ceil() as i64,
+        );
+        if tx0 >= tx1 || ty0 >= ty1 {
+            return;
+        }
+
+        let mut raster = Raster::new(tx0, ty0, (tx1 - tx0) as usize, (ty1 - ty0) as usize, level);
+        raster.accumulate(root, -half as i64, -half as i64);
+        let theme = settings.theme.theme();
+        let image = ColorImage {
+            size: [raster.width, raster.height],
+            pixels: raster.density.iter().map(|&d| shade(&theme, d)).collect(),
+        };
+        let texture = upload(&mut self.texture, ctx, "quadtree", image);
+        let region = Rect::from_min_max(
+            to_screen(tx0 as f64 * texel, ty0 as f64 * texel),
+            to_screen(tx1 as f64 * texel, ty1 as f64 * texel),
+        );
+        painter.image(
+            texture.id(),
+            region,
+            Rect::from_min_max(Pos2::ZERO, egui::pos2(1.0, 1.0)),
+            Color32::WHITE,
+        );
+
+        if level == 0 && settings.grid_visible(scale as f32) {
+            let stroke = egui::Stroke::new(0.5, theme.grid);
+            for x in tx0..=tx1 {
+                let x = to_screen(x as f64, 0.0).x;
+                painter.line_segment([egui::pos2(x, region.min.y), egui::pos2(x, region.max.y)], stroke);
+            }
+            for y in ty0..=ty1 {
+                let y = to_screen(0.0, y as f64).y;
+                painter.line_segment([egui::pos2(region.min.x, y), egui::pos2(region.max.x, y)], stroke);
+            }
+        }
+    }
+}
+
+/// 以材質像素為單位的矩形範圍內，每個材質像素的活細胞比例
+struct Raster {
+    x0: i64,
+    y0: i64,
+    width: usize,
+    height: usize,
+    /// 材質像素邊長為 2^level 個細胞
+    level: u32,
+    density: Vec<f32>,
+}
+
+impl Raster {
+    fn new(x0: i64, y0: i64, width: usize, height: usize, level: u32) -> Self {
+        Raster {
+            x0,
+            y0,
+            width,
+            height,
+            level,
+            density: vec![0.0; width * height],
+        }
+    }
+
+    /// 把左上角位於 (x, y) 的節點累加到它覆蓋的材質像素；範圍外或全空的子樹直接略過
+    fn accumulate(&mut self, node: &ParallelNode, x: i64, y: i64) {
+        let size = 1i64 << node.level();
+        let (min_x, min_y) = (self.x0 << self.level, self.y0 << self.level);
+        let max_x = (self.x0 + self.width as i64) << self.level;
+        let max_y = (self.y0 + self.height as i64) << self.level;
+        if node.population() == 0 || x >= max_x || y >= max_y || x + size <= min_x || y + size <= min_y {
+            return;
+        }
+        match node.children() {
+            Some(children) if node.level() > self.level => {
+                let half = size / 2;
+                let offsets = [(0, 0), (half, 0), (0, half), (half, half)];
+                for (child, (dx, dy)) in children.iter().zip(offsets) {
+                    self.accumulate(child, x + dx, y + dy);
+                }
+            }
+            _ => {
+                let col = ((x >> self.level) - self.x0) as usize;
+                let row = ((y >> self.level) - self.y0) as usize;
+                let area = 4f64.powi(self.level as i32);
+                self.density[row * self.width + col] += (node.population() as f64 / area) as f32;
+            }
+        }
+    }
+}
+
+/// 有活細胞的材質像素至少保留一些亮度，否則極稀疏的遠景會完全看不見
+const MIN_SHADE: f32 = 0.25;
+
+fn shade(theme: &Theme, density: f32) -> Color32 {
+    if density <= 0.0 {
+        return theme.dead;
+    }
+    let t = MIN_SHADE + (1.0 - MIN_SHADE) * density.min(1.0);
+    let mix = |dead: u8, alive: u8| (dead as f32 + (alive as f32 - dead as f32) * t).round() as u8;
+    Color32::from_rgb(
+        mix(theme.dead.r(), theme.alive.r()),
+        mix(theme.dead.g(), theme.alive.g()),
+        mix(theme.dead.b(), theme.alive.b()),
+    )
+}
+
+/// 更新既有材質，第一次繪製時才建立
+fn upload<'a>(
+    texture: &'a mut Option<TextureHandle>,
+    ctx: &egui::Context,
+    name: &str,
+    image: ColorImage,
+) -> &'a TextureHandle {
+    match texture {
+        Some(texture) => {
+            texture.set(image, TextureOptions::NEAREST);
+            texture
+        }
+        None => texture.insert(ctx.load_texture(name, image, TextureOptions::NEAREST)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Raster;
+    use crate::hashlife::HashLifeUniverse;
+
+    /// 邊長 2^40 的圖樣，只在左上角與右下角各有一個活細胞
+    fn corners() -> HashLifeUniverse {
+        let mut text = String::from("[M2]\n#R B3/S23\n*$\n$$$$$$$.......*$\n");
+        let (mut nw, mut se) = (1, 2);
+        for level in 4..40 {
+            let next = nw.max(se) + 1;
+            text += &format!("{} {} 0 0 0\n{} 0 0 0 {}\n", level, nw, level, se);
+            (nw, se) = (next, next + 1);
+        }
+        text += &format!("40 {} 0 0 {}\n", nw, se);
+        HashLifeUniverse::from_macrocell(&text).unwrap()
+    }
+
+    #[test]
+    fn raster_shades_huge_nodes_by_population() {
+        let universe = corners();
+        let root = universe.root();
+        assert_eq!((root.level(), root.population()), (40, 2));
+
+        let half = 1i64 << 39;
+        let mut raster = Raster::new(-1, -1, 2, 2, 39);
+        raster.accumulate(root, -half, -half);
+        let cell = 0.25f32.powi(39);
+        assert_eq!(raster.density, vec![cell, 0.0, 0.0, cell]);
+    }
+
+    #[test]
+    fn raster_descends_to_single_cells_in_view() {
+        let universe = corners();
+        let half = 1i64 << 39;
+        let mut raster = Raster::new(half - 2, half - 2, 2, 2, 0);
+        raster.accumulate(universe.root(), -half, -half);
+        assert_eq!(raster.density, vec![0.0, 0.0, 0.0, 1.0]);
+    }
+}
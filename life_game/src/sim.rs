@@ -0,0 +1,423 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hashlife_br::ParallelNode;
+
+use crate::hashlife::HashLifeUniverse;
+use crate::Universe;
+
+/// 背景執行緒最多以此頻率回傳快照，避免每一步都複製整個棋盤
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(16);
+
+/// 大步長時每前進這麼多代就檢查一次指令，暫停與載入編輯後的棋盤不必等整步算完
+const CHUNK: u64 = 64;
+
+/// UI 傳給模擬執行緒的指令
+enum Command {
+    /// 以 UI 編輯後的棋盤取代目前狀態
+    Load { universe: Universe, generation: u64, epoch: u64 },
+    Run(bool),
+    /// 暫停時手動前進一個步長
+    Step,
+    Configure { generations_per_second: f32, step_exponent: u32 },
+}
+
+/// 模擬執行緒回傳的狀態
+pub struct Snapshot {
+    pub universe: Universe,
+    pub generation: u64,
+    /// 最近一秒實際達到的世代速度
+    pub measured_rate: f32,
+    epoch: u64,
+}
+
+/// 在背景執行緒推進世代，與 UI 的幀率脫鉤
+pub struct Simulation {
+    commands: Sender<Command>,
+    snapshots: Receiver<Snapshot>,
+    epoch: u64,
+}
+
+impl Simulation {
+    pub fn spawn(universe: Universe) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (snapshot_tx, snapshots) = mpsc::channel();
+        thread::Builder::new()
+            .name("life-simulation".to_owned())
+            .spawn(move || Worker::new(universe, command_rx, snapshot_tx).run())
+            .expect("failed to spawn simulation thread");
+        Simulation {
+            commands,
+            snapshots,
+            epoch: 0,
+        }
+    }
+
+    fn send(&self, command: Command) {
+        // 執行緒只會在 Simulation 被丟棄後結束，送出失敗可以忽略
+        let _ = self.commands.send(command);
+    }
+
+    /// 把 UI 端修改過的棋盤同步到模擬執行緒；較舊的快照之後會被丟棄
+    pub fn load(&mut self, universe: Universe, generation: u64) {
+        self.epoch += 1;
+        self.send(Command::Load {
+            universe,
+            generation,
+            epoch: self.epoch,
+        });
+    }
+
+    pub fn set_running(&self, running: bool) {
+        self.send(Command::Run(running));
+    }
+
+    pub fn step(&self) {
+        self.send(Command::Step);
+    }
+
+    pub fn configure(&self, generations_per_second: f32, step_exponent: u32) {
+        self.send(Command::Configure {
+            generations_per_second,
+            step_exponent,
+        });
+    }
+
+    /// 取出最新的快照，略過在最後一次 `load` 之前產生的
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.snapshots
+            .try_iter()
+            .filter(|snapshot| snapshot.epoch == self.epoch)
+            .last()
+    }
+}
+
+struct Worker {
+    universe: Universe,
+    generation: u64,
+    epoch: u64,
+    running: bool,
+    generations_per_second: f32,
+    step_size: u64,
+    next_step: Instant,
+    last_snapshot: Instant,
+    rate_window: (Instant, u64),
+    measured_rate: f32,
+    /// 前進到一半時收到、還沒處理的指令，依收到的順序處理
+    queued: VecDeque<Command>,
+    commands: Receiver<Command>,
+    snapshots: Sender<Snapshot>,
+}
+
+impl Worker {
+    fn new(universe: Universe, commands: Receiver<Command>, snapshots: Sender<Snapshot>) -> Self {
+        let now = Instant::now();
+        Worker {
+            universe,
+            generation: 0,
+            epoch: 0,
+            running: false,
+            generations_per_second: 10.0,
+            step_size: 1,
+            next_step: now,
+            last_snapshot: now,
+            rate_window: (now, 0),
+            measured_rate: 0.0,
+            queued: VecDeque::new(),
+            commands,
+            snapshots,
+        }
+    }
+
+    fn run(mut self) {
+        loop {
+            let command = if let Some(command) = self.queued.pop_front() {
+                Some(command)
+            } else if self.running {
+                let now = Instant::now();
+                if self.next_step > now {
+                    match self.commands.recv_timeout(self.next_step - now) {
+                        Ok(command) => Some(command),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                } else {
+                    match self.commands.try_recv() {
+                        Ok(command) => Some(command),
+                        Err(TryRecvError::Empty) => None,
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                }
+            } else {
+                match self.commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => return,
+                }
+            };
+
+            match command {
+                Some(command) => self.handle(command),
+                None => {
+                    self.advance();
+                    let interval = Duration::from_secs_f64(self.step_size as f64 / self.generations_per_second as f64);
+                    let now = Instant::now();
+                    // 跟不上目標速度時不累積欠下的步數，直接全速執行
+                    self.next_step = (self.next_step + interval).max(now.checked_sub(interval).unwrap_or(now));
+                    if now - self.last_snapshot >= SNAPSHOT_INTERVAL {
+                        self.publish();
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Load {
+                universe,
+                generation,
+                epoch,
+            } => {
+                self.universe = universe;
+                self.generation = generation;
+                self.epoch = epoch;
+                self.rate_window = (Instant::now(), generation);
+            }
+            Command::Run(running) => {
+                self.running = running;
+                self.next_step = Instant::now();
+                self.rate_window = (self.next_step, self.generation);
+                if !running {
+                    self.measured_rate = 0.0;
+                    self.publish();
+                }
+            }
+            Command::Step => {
+                self.advance();
+                self.publish();
+            }
+            Command::Configure {
+                generations_per_second,
+                step_exponent,
+            } => {
+                self.generations_per_second = generations_per_second.max(f32::MIN_POSITIVE);
+                self.step_size = 1 << step_exponent.min(20);
+            }
+        }
+    }
+
+    /// 前進一個步長。途中收到的指令留給主迴圈依序處理；只有載入新棋盤與暫停會讓這一步
+    /// 停在已完成的世代，其他指令（例如拖動速度滑桿）等整步算完，世代數維持步長的倍數
+    fn advance(&mut self) {
+        let mut done = 0;
+        'step: while done < self.step_size {
+            let chunk = CHUNK.min(self.step_size - done);
+            for _ in 0..chunk {
+                self.universe.tick();
+            }
+            done += chunk;
+            if done < self.step_size {
+                while let Ok(command) = self.commands.try_recv() {
+                    let interrupts = matches!(command, Command::Load { .. } | Command::Run(false));
+                    self.queued.push_back(command);
+                    if interrupts {
+                        break 'step;
+                    }
+                }
+            }
+        }
+        self.generation += done;
+
+        let (start, generation) = self.rate_window;
+        let elapsed = start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.measured_rate = (self.generation - generation) as f32 / elapsed.as_secs_f32();
+            self.rate_window = (Instant::now(), self.generation);
+        }
+    }
+
+    fn publish(&mut self) {
+        self.last_snapshot = Instant::now();
+        let _ = self.snapshots.send(Snapshot {
+            universe: self.universe.clone(),
+            generation: self.generation,
+            measured_rate: self.measured_rate,
+            epoch: self.epoch,
+        });
+    }
+}
+
+/// UI 傳給四叉樹執行緒的指令
+enum TreeCommand {
+    Step(u64),
+    Toggle(i64, i64),
+    Clear,
+}
+
+/// 四叉樹執行緒回傳的狀態；節點不可變，只需共享根節點
+pub struct TreeSnapshot {
+    pub root: Arc<ParallelNode>,
+    pub generation: u64,
+    /// 指令因圖樣超出範圍而沒有完成時的原因
+    pub error: Option<String>,
+}
+
+/// 在背景執行緒推進 HashLife，大步長的跳躍不會卡住 UI
+pub struct TreeSimulation {
+    commands: Sender<TreeCommand>,
+    snapshots: Receiver<TreeSnapshot>,
+    /// 已送出但還沒收到結果的指令數
+    pending: usize,
+}
+
+impl TreeSimulation {
+    pub fn spawn(mut universe: HashLifeUniverse) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (snapshot_tx, snapshots) = mpsc::channel();
+        thread::Builder::new()
+            .name("life-hashlife".to_owned())
+            .spawn(move || {
+                for command in command_rx {
+                    let result = match command {
+                        TreeCommand::Step(generations) => universe.step(generations),
+                        TreeCommand::Toggle(x, y) => {
+                            let alive = universe.get_cell(x, y);
+                            universe.set_cell(x, y, !alive)
+                        }
+                        TreeCommand::Clear => {
+                            universe.clear();
+                            Ok(())
+                        }
+                    };
+                    let snapshot = TreeSnapshot {
+                        root: universe.root().clone(),
+                        generation: universe.generation(),
+                        error: result.err(),
+                    };
+                    if snapshot_tx.send(snapshot).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn hashlife thread");
+        TreeSimulation {
+            commands,
+            snapshots,
+            pending: 0,
+        }
+    }
+
+    fn send(&mut self, command: TreeCommand) {
+        if self.commands.send(command).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    pub fn step(&mut self, generations: u64) {
+        self.send(TreeCommand::Step(generations));
+    }
+
+    pub fn toggle(&mut self, x: i64, y: i64) {
+        self.send(TreeCommand::Toggle(x, y));
+    }
+
+    pub fn clear(&mut self) {
+        self.send(TreeCommand::Clear);
+    }
+
+    /// 背景執行緒是否還在處理指令
+    pub fn busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// 取出最新的快照
+    pub fn latest(&mut self) -> Option<TreeSnapshot> {
+        self.snapshots.try_iter().inspect(|_| self.pending -= 1).last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Simulation, TreeSimulation};
+    use crate::hashlife::HashLifeUniverse;
+    use crate::Universe;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn load_interrupts_a_long_step() {
+        let mut sim = Simulation::spawn(Universe::new(64, 64));
+        // 2^20 代在這個棋盤上要算很久，Load 必須在步長中途就被處理
+        sim.configure(1.0, 20);
+        sim.step();
+        sim.load(Universe::new(8, 8), 5);
+        sim.set_running(false);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let snapshot = loop {
+            if let Some(snapshot) = sim.latest() {
+                break snapshot;
+            }
+            assert!(Instant::now() < deadline, "load was not handled during the step");
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(snapshot.generation, 5);
+        assert_eq!(snapshot.universe.width(), 8);
+    }
+
+    #[test]
+    fn other_commands_wait_for_the_step_to_finish() {
+        let mut sim = Simulation::spawn(Universe::new(16, 16));
+        sim.configure(1.0, 12);
+        sim.load(Universe::new(16, 16), 0);
+        sim.step();
+        sim.configure(2.0, 12);
+        sim.step();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut generation = 0;
+        while generation < 2 << 12 {
+            if let Some(snapshot) = sim.latest() {
+                assert_eq!(snapshot.generation % (1 << 12), 0);
+                generation = snapshot.generation;
+            }
+            assert!(Instant::now() < deadline, "steps did not finish");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(generation, 2 << 12);
+    }
+
+    #[test]
+    fn tree_simulation_steps_in_background() {
+        let mut universe = HashLifeUniverse::new();
+        for x in -1..=1 {
+            universe.set_cell(x, 0, true).unwrap();
+        }
+        let mut sim = TreeSimulation::spawn(universe);
+        sim.step(1);
+        sim.toggle(5, 5);
+        assert!(sim.busy());
+
+        let mut latest = None;
+        while sim.busy() {
+            latest = sim.latest().or(latest);
+            std::thread::yield_now();
+        }
+        let snapshot = latest.unwrap();
+        assert_eq!(snapshot.generation, 1);
+        assert_eq!(snapshot.root.population(), 4);
+        assert!(snapshot.error.is_none());
+
+        sim.toggle(i64::MAX, 0);
+        let mut latest = None;
+        while sim.busy() {
+            latest = sim.latest().or(latest);
+            std::thread::yield_now();
+        }
+        let snapshot = latest.unwrap();
+        assert!(snapshot.error.is_some());
+        assert_eq!(snapshot.root.population(), 4);
+    }
+}
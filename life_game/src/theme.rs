@@ -0,0 +1,139 @@
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+/// 內建的配色主題
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Classic,
+    Ocean,
+    Amber,
+    Paper,
+}
+
+/// 細胞的著色方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    /// 只區分生死
+    State,
+    /// 依存活世代數或死亡後經過的世代數著色
+    Age,
+}
+
+pub struct Theme {
+    pub alive: Color32,
+    pub dead: Color32,
+    pub grid: Color32,
+    /// 剛誕生的細胞
+    pub young: Color32,
+    /// 存活很久的細胞
+    pub old: Color32,
+    /// 剛死亡的細胞殘影
+    pub dying: Color32,
+}
+
+impl ThemeKind {
+    pub const ALL: [ThemeKind; 4] = [ThemeKind::Classic, ThemeKind::Ocean, ThemeKind::Amber, ThemeKind::Paper];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeKind::Classic => "經典",
+            ThemeKind::Ocean => "海洋",
+            ThemeKind::Amber => "琥珀",
+            ThemeKind::Paper => "紙張",
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeKind::Classic => Theme {
+                alive: Color32::from_rgb(0, 255, 0),
+                dead: Color32::from_rgb(20, 20, 20),
+                grid: Color32::from_gray(100),
+                young: Color32::from_rgb(180, 255, 120),
+                old: Color32::from_rgb(0, 110, 40),
+                dying: Color32::from_rgb(120, 40, 40),
+            },
+            ThemeKind::Ocean => Theme {
+                alive: Color32::from_rgb(80, 200, 255),
+                dead: Color32::from_rgb(8, 18, 36),
+                grid: Color32::from_rgb(30, 50, 80),
+                young: Color32::from_rgb(200, 245, 255),
+                old: Color32::from_rgb(20, 80, 180),
+                dying: Color32::from_rgb(60, 40, 110),
+            },
+            ThemeKind::Amber => Theme {
+                alive: Color32::from_rgb(255, 176, 0),
+                dead: Color32::from_rgb(24, 16, 8),
+                grid: Color32::from_rgb(70, 50, 20),
+                young: Color32::from_rgb(255, 230, 140),
+                old: Color32::from_rgb(180, 80, 0),
+                dying: Color32::from_rgb(90, 30, 10),
+            },
+            ThemeKind::Paper => Theme {
+                alive: Color32::from_rgb(30, 30, 30),
+                dead: Color32::from_rgb(245, 242, 232),
+                grid: Color32::from_rgb(200, 195, 185),
+                young: Color32::from_rgb(90, 90, 200),
+                old: Color32::from_rgb(10, 10, 40),
+                dying: Color32::from_rgb(230, 180, 170),
+            },
+        }
+    }
+}
+
+/// 可持久化的繪製設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderSettings {
+    pub theme: ThemeKind,
+    pub color_mode: ColorMode,
+    pub show_grid: bool,
+    /// 細胞小於此像素數時自動隱藏網格
+    pub grid_min_cell_size: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            theme: ThemeKind::Classic,
+            color_mode: ColorMode::State,
+            show_grid: true,
+            grid_min_cell_size: 5.0,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub const STORAGE_KEY: &'static str = "render_settings";
+
+    /// 存活顏色漸變所涵蓋的世代數
+    const AGE_SPAN: f32 = 64.0;
+    /// 死亡殘影持續的世代數
+    const FADE_SPAN: u16 = 12;
+
+    pub fn grid_visible(&self, cell_size: f32) -> bool {
+        self.show_grid && cell_size >= self.grid_min_cell_size
+    }
+
+    /// 依目前的著色模式決定細胞顏色；`age` 的意義見 `Universe::get_age`
+    pub fn cell_color(&self, theme: &Theme, alive: bool, age: u16) -> Color32 {
+        match (self.color_mode, alive) {
+            (ColorMode::State, true) => theme.alive,
+            (ColorMode::State, false) => theme.dead,
+            (ColorMode::Age, true) => {
+                // 以對數尺度漸變，讓前幾個世代的變化更明顯
+                let t = ((age as f32 + 1.0).ln() / (Self::AGE_SPAN + 1.0).ln()).min(1.0);
+                lerp_color(theme.young, theme.old, t)
+            }
+            (ColorMode::Age, false) if age < Self::FADE_SPAN => {
+                let t = age as f32 / Self::FADE_SPAN as f32;
+                lerp_color(theme.dying, theme.dead, t)
+            }
+            (ColorMode::Age, false) => theme.dead,
+        }
+    }
+}
+
+fn lerp_color(from: Color32, to: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgb(mix(from.r(), to.r()), mix(from.g(), to.g()), mix(from.b(), to.b()))
+}